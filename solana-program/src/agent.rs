@@ -0,0 +1,839 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    settlement_destination, transfer_from_vault, vault_balance, Dispute, EscrowError,
+    ProtocolConfig, VaultAccounts, BPS_DENOMINATOR, MAX_DISPUTE_WINDOW, MAX_REVIEW_WINDOW,
+    NATIVE_SOL_MINT,
+};
+
+/// Share of an agent's stake slashed when the panel overturns its verdict (10%)
+pub const DEFAULT_AGENT_SLASH_BPS: u16 = 1_000;
+/// Shortest wait between an agent's last verdict or unbonding request and
+/// withdrawing its stake: a pass can be disputed for up to the longest review
+/// window, and the panel then has up to the longest dispute window to overturn it
+pub const MIN_AGENT_UNBONDING_PERIOD: i64 = MAX_REVIEW_WINDOW + MAX_DISPUTE_WINDOW;
+/// Maximum number of signing keys an agent holds at once
+pub const MAX_SIGNING_KEYS: usize = 4;
+
+/// Registry entry for an AI verification agent
+#[account]
+#[derive(InitSpace)]
+pub struct AiAgent {
+    /// Identity escrows name the agent by; its first signing key
+    pub agent: Pubkey,
+    /// Registers the agent, funds its stake and maintains its metadata
+    pub operator: Pubkey,
+    /// Native SOL stakes are held on this account itself
+    pub stake_mint: Pubkey,
+    pub stake_amount: u64,
+    pub model_id: [u8; 32],
+    pub endpoint_hash: [u8; 32],
+    pub status: AgentStatus,
+    pub verdicts_overturned: u32,
+    pub registered_at: i64,
+    /// When the agent last submitted a verdict that counted
+    pub last_verdict_at: Option<i64>,
+    /// When the operator started unbonding the agent's stake
+    pub unbonding_started_at: Option<i64>,
+    /// Disputes over the agent's verdicts not yet finalized, or finalized against
+    /// it and not yet slashed. Its stake can't be withdrawn while any are open.
+    pub open_disputes: u16,
+    /// Keys the agent signs verdicts with, each valid for a window
+    #[max_len(MAX_SIGNING_KEYS)]
+    pub signing_keys: Vec<SigningKey>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct SigningKey {
+    pub key: Pubkey,
+    pub valid_from: i64,
+    /// `None` until the key is revoked or given an expiry
+    pub valid_until: Option<i64>,
+}
+
+impl SigningKey {
+    pub fn is_valid_at(&self, now: i64) -> bool {
+        self.valid_from <= now && self.valid_until.is_none_or(|valid_until| now < valid_until)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum AgentStatus {
+    Active,
+    Suspended,
+    /// Retired by its operator; takes no new verdicts and may withdraw its stake
+    /// once the unbonding period has passed
+    Unbonding,
+}
+
+impl AiAgent {
+    pub fn is_native(&self) -> bool {
+        self.stake_mint == NATIVE_SOL_MINT
+    }
+
+    /// Whether `key` may sign verdicts for this agent at `now`
+    pub fn is_signing_key(&self, key: &Pubkey, now: i64) -> bool {
+        self.signing_keys
+            .iter()
+            .any(|signing_key| signing_key.key == *key && signing_key.is_valid_at(now))
+    }
+
+    /// Hold the stake for a newly opened dispute over one of the agent's verdicts
+    pub fn add_open_dispute(&mut self) -> Result<()> {
+        self.open_disputes = self.open_disputes
+            .checked_add(1)
+            .ok_or(EscrowError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Release a dispute's hold once it can no longer slash the agent
+    pub fn release_open_dispute(&mut self) -> Result<()> {
+        self.open_disputes = self.open_disputes
+            .checked_sub(1)
+            .ok_or(EscrowError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+/// Check that each of `agents` is registered and active, given their registry
+/// entries in the same order
+pub(crate) fn require_registered_agents<'info>(
+    agents: &[Pubkey],
+    entries: &'info [AccountInfo<'info>],
+) -> Result<()> {
+    require!(entries.len() >= agents.len(), EscrowError::UnregisteredAgent);
+    for (agent, entry) in agents.iter().zip(entries) {
+        let entry = Account::<AiAgent>::try_from(entry)?;
+        require_keys_eq!(entry.agent, *agent, EscrowError::UnregisteredAgent);
+        require!(entry.status == AgentStatus::Active, EscrowError::AgentSuspended);
+    }
+    
+    Ok(())
+}
+
+/// Load the registry entry passed for `agent`
+fn agent_entry<'info>(
+    agent: &Pubkey,
+    entry: &'info AccountInfo<'info>,
+) -> Result<Account<'info, AiAgent>> {
+    let entry = Account::<AiAgent>::try_from(entry)?;
+    require_keys_eq!(entry.agent, *agent, EscrowError::UnregisteredAgent);
+    Ok(entry)
+}
+
+/// Hold the stake of each of `agents` for a newly opened dispute, given their
+/// registry entries in the same order
+pub(crate) fn add_agent_disputes<'info>(
+    agents: &[Pubkey],
+    entries: &'info [AccountInfo<'info>],
+) -> Result<()> {
+    require!(entries.len() >= agents.len(), EscrowError::UnregisteredAgent);
+    for (agent, entry) in agents.iter().zip(entries) {
+        let mut entry = agent_entry(agent, entry)?;
+        entry.add_open_dispute()?;
+        entry.exit(&crate::ID)?;
+    }
+    
+    Ok(())
+}
+
+/// Release a finalized dispute's hold on each of `agents`, given their registry
+/// entries in the same order. Agents in `overturned` stay held until slashed.
+pub(crate) fn release_agent_disputes<'info>(
+    agents: &[Pubkey],
+    entries: &'info [AccountInfo<'info>],
+    overturned: &[Pubkey],
+) -> Result<()> {
+    require!(entries.len() >= agents.len(), EscrowError::UnregisteredAgent);
+    for (agent, entry) in agents.iter().zip(entries) {
+        if overturned.contains(agent) {
+            continue;
+        }
+        let mut entry = agent_entry(agent, entry)?;
+        entry.release_open_dispute()?;
+        entry.exit(&crate::ID)?;
+    }
+    
+    Ok(())
+}
+
+/// Register an agent with its initial stake in the protocol's stake mint.
+/// The agent key co-signs to prove the operator controls it.
+pub fn register_agent<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RegisterAgent<'info>>,
+    model_id: [u8; 32],
+    endpoint_hash: [u8; 32],
+    stake_amount: u64,
+) -> Result<()> {
+    let agent_account_info = ctx.accounts.ai_agent.to_account_info();
+    let config = &ctx.accounts.config;
+    let stake_mint = config.agent_stake_mint;
+    let stake_vault = VaultAccounts::resolve(
+        stake_mint == NATIVE_SOL_MINT,
+        ctx.accounts.token_program.as_ref(),
+        ctx.accounts.stake_vault.as_ref(),
+        ctx.accounts.stake_mint.as_ref(),
+        ctx.remaining_accounts,
+    )?;
+    
+    let staked = deposit_stake(
+        stake_vault.as_ref(),
+        ctx.accounts.operator_token_account.as_ref(),
+        ctx.accounts.operator.to_account_info(),
+        agent_account_info,
+        ctx.accounts.system_program.to_account_info(),
+        stake_amount,
+    )?;
+    require!(staked >= config.min_agent_stake, EscrowError::InsufficientAgentStake);
+    
+    let ai_agent = &mut ctx.accounts.ai_agent;
+    ai_agent.agent = ctx.accounts.agent.key();
+    ai_agent.operator = ctx.accounts.operator.key();
+    ai_agent.stake_mint = stake_mint;
+    ai_agent.stake_amount = staked;
+    ai_agent.model_id = model_id;
+    ai_agent.endpoint_hash = endpoint_hash;
+    ai_agent.status = AgentStatus::Active;
+    ai_agent.verdicts_overturned = 0;
+    ai_agent.registered_at = Clock::get()?.unix_timestamp;
+    ai_agent.last_verdict_at = None;
+    ai_agent.unbonding_started_at = None;
+    ai_agent.open_disputes = 0;
+    ai_agent.signing_keys = vec![SigningKey {
+        key: ai_agent.agent,
+        valid_from: ai_agent.registered_at,
+        valid_until: None,
+    }];
+    ai_agent.bump = ctx.bumps.ai_agent;
+    
+    emit!(AgentRegistered {
+        agent: ai_agent.agent,
+        operator: ai_agent.operator,
+        stake_mint,
+        stake_amount: staked,
+        model_id,
+        endpoint_hash,
+    });
+    
+    Ok(())
+}
+
+/// Top up an agent's stake
+pub fn add_agent_stake<'info>(
+    ctx: Context<'_, '_, 'info, 'info, AddAgentStake<'info>>,
+    amount: u64,
+) -> Result<()> {
+    let agent_account_info = ctx.accounts.ai_agent.to_account_info();
+    let stake_vault = VaultAccounts::resolve(
+        ctx.accounts.ai_agent.is_native(),
+        ctx.accounts.token_program.as_ref(),
+        ctx.accounts.stake_vault.as_ref(),
+        ctx.accounts.stake_mint.as_ref(),
+        ctx.remaining_accounts,
+    )?;
+    
+    let staked = deposit_stake(
+        stake_vault.as_ref(),
+        ctx.accounts.operator_token_account.as_ref(),
+        ctx.accounts.operator.to_account_info(),
+        agent_account_info,
+        ctx.accounts.system_program.to_account_info(),
+        amount,
+    )?;
+    
+    let ai_agent = &mut ctx.accounts.ai_agent;
+    ai_agent.stake_amount = ai_agent.stake_amount
+        .checked_add(staked)
+        .ok_or(EscrowError::MathOverflow)?;
+    
+    emit!(AgentStakeChanged {
+        agent: ai_agent.agent,
+        stake_amount: ai_agent.stake_amount,
+    });
+    
+    Ok(())
+}
+
+/// Operator updates the agent's model identifier and endpoint hash
+pub fn update_agent_metadata(
+    ctx: Context<UpdateAgent>,
+    model_id: [u8; 32],
+    endpoint_hash: [u8; 32],
+) -> Result<()> {
+    let ai_agent = &mut ctx.accounts.ai_agent;
+    ai_agent.model_id = model_id;
+    ai_agent.endpoint_hash = endpoint_hash;
+    
+    emit!(AgentMetadataUpdated {
+        agent: ai_agent.agent,
+        model_id,
+        endpoint_hash,
+    });
+    
+    Ok(())
+}
+
+/// Operator adds a signing key valid from `valid_from` until `valid_until`, if
+/// given. Keys that have already expired are pruned to make room.
+pub fn add_agent_signing_key(
+    ctx: Context<UpdateAgent>,
+    key: Pubkey,
+    valid_from: i64,
+    valid_until: Option<i64>,
+) -> Result<()> {
+    let ai_agent = &mut ctx.accounts.ai_agent;
+    require!(
+        valid_until.is_none_or(|valid_until| valid_from < valid_until),
+        EscrowError::InvalidSigningKey
+    );
+    
+    let clock = Clock::get()?;
+    ai_agent.signing_keys.retain(|signing_key| {
+        signing_key
+            .valid_until
+            .is_none_or(|valid_until| clock.unix_timestamp < valid_until)
+    });
+    require!(
+        !ai_agent.signing_keys.iter().any(|signing_key| signing_key.key == key),
+        EscrowError::InvalidSigningKey
+    );
+    require!(
+        ai_agent.signing_keys.len() < MAX_SIGNING_KEYS,
+        EscrowError::TooManySigningKeys
+    );
+    
+    ai_agent.signing_keys.push(SigningKey {
+        key,
+        valid_from,
+        valid_until,
+    });
+    
+    emit!(AgentSigningKeyAdded {
+        agent: ai_agent.agent,
+        key,
+        valid_from,
+        valid_until,
+    });
+    
+    Ok(())
+}
+
+/// Operator revokes a signing key, effective immediately
+pub fn revoke_agent_signing_key(ctx: Context<UpdateAgent>, key: Pubkey) -> Result<()> {
+    let ai_agent = &mut ctx.accounts.ai_agent;
+    let clock = Clock::get()?;
+    
+    let signing_key = ai_agent
+        .signing_keys
+        .iter_mut()
+        .find(|signing_key| signing_key.key == key)
+        .ok_or(EscrowError::UnknownSigningKey)?;
+    let revoked_at = signing_key
+        .valid_until
+        .map_or(clock.unix_timestamp, |valid_until| valid_until.min(clock.unix_timestamp));
+    signing_key.valid_until = Some(revoked_at);
+    
+    emit!(AgentSigningKeyRevoked {
+        agent: ai_agent.agent,
+        key,
+        revoked_at,
+    });
+    
+    Ok(())
+}
+
+/// Authority suspends or reinstates an agent. Reinstated agents must hold the minimum stake.
+/// Unbonding is left to the operator.
+pub fn set_agent_status(ctx: Context<SetAgentStatus>, status: AgentStatus) -> Result<()> {
+    let ai_agent = &mut ctx.accounts.ai_agent;
+    require!(
+        status != AgentStatus::Unbonding && ai_agent.status != AgentStatus::Unbonding,
+        EscrowError::AgentUnbonding
+    );
+    if status == AgentStatus::Active {
+        require!(
+            ai_agent.stake_amount >= ctx.accounts.config.min_agent_stake,
+            EscrowError::InsufficientAgentStake
+        );
+    }
+    
+    ai_agent.status = status;
+    
+    emit!(AgentStatusChanged {
+        agent: ai_agent.agent,
+        status,
+    });
+    
+    Ok(())
+}
+
+/// Slash an agent whose verdict a dispute panel overturned, paying the treasury.
+/// Agents left below the minimum stake are suspended.
+pub fn slash_agent<'info>(ctx: Context<'_, '_, 'info, 'info, SlashAgent<'info>>) -> Result<()> {
+    let agent_account_info = ctx.accounts.ai_agent.to_account_info();
+    let is_native = ctx.accounts.ai_agent.is_native();
+    let stake_vault = VaultAccounts::resolve(
+        is_native,
+        ctx.accounts.token_program.as_ref(),
+        ctx.accounts.stake_vault.as_ref(),
+        ctx.accounts.stake_mint.as_ref(),
+        ctx.remaining_accounts,
+    )?;
+    let treasury_destination = settlement_destination(
+        is_native,
+        ctx.accounts.protocol_treasury.as_ref(),
+        ctx.accounts.treasury_wallet.as_ref(),
+    )?;
+    let config = &ctx.accounts.config;
+    let dispute = &mut ctx.accounts.dispute;
+    let ai_agent = &mut ctx.accounts.ai_agent;
+    
+    let slash_amount = (ai_agent.stake_amount as u128)
+        .checked_mul(config.agent_slash_bps as u128)
+        .ok_or(EscrowError::MathOverflow)?
+        .checked_div(BPS_DENOMINATOR as u128)
+        .ok_or(EscrowError::MathOverflow)? as u64;
+    
+    // Store immutable values to avoid borrow conflicts
+    let agent = ai_agent.agent;
+    let bump = ai_agent.bump;
+    
+    let seeds = &[
+        b"agent",
+        agent.as_ref(),
+        &[bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    transfer_from_vault(
+        stake_vault.as_ref(),
+        treasury_destination,
+        agent_account_info,
+        signer,
+        slash_amount,
+    )?;
+    
+    ai_agent.stake_amount = ai_agent.stake_amount
+        .checked_sub(slash_amount)
+        .ok_or(EscrowError::MathOverflow)?;
+    ai_agent.verdicts_overturned = ai_agent.verdicts_overturned.saturating_add(1);
+    if ai_agent.status == AgentStatus::Active && ai_agent.stake_amount < config.min_agent_stake {
+        ai_agent.status = AgentStatus::Suspended;
+    }
+    dispute.overturned_agents.retain(|overturned| *overturned != agent);
+    ai_agent.release_open_dispute()?;
+    
+    emit!(AgentSlashed {
+        agent,
+        dispute: dispute.key(),
+        amount: slash_amount,
+        stake_amount: ai_agent.stake_amount,
+        status: ai_agent.status,
+    });
+    
+    Ok(())
+}
+
+/// Operator retires an agent and starts the unbonding period on its stake
+pub fn begin_agent_unbonding(ctx: Context<UpdateAgent>) -> Result<()> {
+    let ai_agent = &mut ctx.accounts.ai_agent;
+    require!(ai_agent.status != AgentStatus::Unbonding, EscrowError::AgentUnbonding);
+    
+    let clock = Clock::get()?;
+    ai_agent.status = AgentStatus::Unbonding;
+    ai_agent.unbonding_started_at = Some(clock.unix_timestamp);
+    
+    emit!(AgentStatusChanged {
+        agent: ai_agent.agent,
+        status: ai_agent.status,
+    });
+    
+    Ok(())
+}
+
+/// Operator withdraws stake from an unbonding agent once the unbonding period
+/// has passed since both the request and the agent's last verdict, so every
+/// verdict it signed has had the chance to be overturned and slashed. Disputes
+/// still open over its verdicts hold the stake past the unbonding period.
+pub fn withdraw_agent_stake<'info>(
+    ctx: Context<'_, '_, 'info, 'info, WithdrawAgentStake<'info>>,
+    amount: u64,
+) -> Result<()> {
+    let agent_account_info = ctx.accounts.ai_agent.to_account_info();
+    let is_native = ctx.accounts.ai_agent.is_native();
+    let stake_vault = VaultAccounts::resolve(
+        is_native,
+        ctx.accounts.token_program.as_ref(),
+        ctx.accounts.stake_vault.as_ref(),
+        ctx.accounts.stake_mint.as_ref(),
+        ctx.remaining_accounts,
+    )?;
+    let operator_destination = if is_native {
+        ctx.accounts.operator.to_account_info()
+    } else {
+        ctx.accounts
+            .operator_token_account
+            .as_ref()
+            .ok_or(EscrowError::MissingSettlementAccount)?
+            .to_account_info()
+    };
+    let unbonding_period = ctx.accounts.config.agent_unbonding_period;
+    let ai_agent = &mut ctx.accounts.ai_agent;
+    
+    let clock = Clock::get()?;
+    let unbonding_started_at = ai_agent
+        .unbonding_started_at
+        .ok_or(EscrowError::AgentNotUnbonded)?;
+    let unbonds_at = unbonding_started_at
+        .max(ai_agent.last_verdict_at.unwrap_or(i64::MIN))
+        .checked_add(unbonding_period)
+        .ok_or(EscrowError::MathOverflow)?;
+    require!(
+        ai_agent.status == AgentStatus::Unbonding && clock.unix_timestamp >= unbonds_at,
+        EscrowError::AgentNotUnbonded
+    );
+    require!(ai_agent.open_disputes == 0, EscrowError::AgentDisputesOpen);
+    require!(amount <= ai_agent.stake_amount, EscrowError::InsufficientAgentStake);
+    
+    // Store immutable values to avoid borrow conflicts
+    let agent = ai_agent.agent;
+    let bump = ai_agent.bump;
+    
+    let seeds = &[
+        b"agent",
+        agent.as_ref(),
+        &[bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    transfer_from_vault(
+        stake_vault.as_ref(),
+        operator_destination,
+        agent_account_info,
+        signer,
+        amount,
+    )?;
+    
+    ai_agent.stake_amount = ai_agent.stake_amount
+        .checked_sub(amount)
+        .ok_or(EscrowError::MathOverflow)?;
+    
+    emit!(AgentStakeChanged {
+        agent,
+        stake_amount: ai_agent.stake_amount,
+    });
+    
+    Ok(())
+}
+
+/// Move `amount` of stake from the operator, returning what the agent
+/// actually received net of any Token-2022 transfer fee
+fn deposit_stake<'info>(
+    stake_vault: Option<&VaultAccounts<'_, 'info>>,
+    operator_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+    operator: AccountInfo<'info>,
+    ai_agent: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    amount: u64,
+) -> Result<u64> {
+    match stake_vault {
+        Some(stake_vault) => {
+            let operator_token_account =
+                operator_token_account.ok_or(EscrowError::MissingSettlementAccount)?;
+            let vault_info = stake_vault.escrow_vault.to_account_info();
+            let balance_before = vault_balance(&vault_info)?;
+            stake_vault.deposit(operator_token_account.to_account_info(), operator, amount)?;
+            vault_balance(&vault_info)?
+                .checked_sub(balance_before)
+                .ok_or_else(|| error!(EscrowError::MathOverflow))
+        },
+        None => {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program,
+                    system_program::Transfer {
+                        from: operator,
+                        to: ai_agent,
+                    },
+                ),
+                amount,
+            )?;
+            Ok(amount)
+        },
+    }
+}
+
+#[derive(Accounts)]
+pub struct RegisterAgent<'info> {
+    #[account(mut)]
+    pub operator: Signer<'info>,
+    /// The agent's identity and first signing key
+    pub agent: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        init,
+        payer = operator,
+        space = 8 + AiAgent::INIT_SPACE,
+        seeds = [b"agent", agent.key().as_ref()],
+        bump
+    )]
+    pub ai_agent: Account<'info, AiAgent>,
+    #[account(address = config.agent_stake_mint)]
+    pub stake_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        constraint = operator_token_account.mint == config.agent_stake_mint,
+        constraint = operator_token_account.owner == operator.key()
+    )]
+    pub operator_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = operator,
+        token::mint = stake_mint,
+        token::authority = ai_agent,
+        token::token_program = token_program,
+        seeds = [b"agent_stake", ai_agent.key().as_ref()],
+        bump
+    )]
+    pub stake_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddAgentStake<'info> {
+    #[account(mut)]
+    pub operator: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"agent", ai_agent.agent.as_ref()],
+        bump = ai_agent.bump,
+        constraint = ai_agent.operator == operator.key() @ EscrowError::UnauthorizedAgentOperator
+    )]
+    pub ai_agent: Account<'info, AiAgent>,
+    #[account(address = ai_agent.stake_mint)]
+    pub stake_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        constraint = operator_token_account.mint == ai_agent.stake_mint,
+        constraint = operator_token_account.owner == operator.key()
+    )]
+    pub operator_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"agent_stake", ai_agent.key().as_ref()],
+        bump
+    )]
+    pub stake_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawAgentStake<'info> {
+    #[account(mut)]
+    pub operator: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        seeds = [b"agent", ai_agent.agent.as_ref()],
+        bump = ai_agent.bump,
+        constraint = ai_agent.operator == operator.key() @ EscrowError::UnauthorizedAgentOperator
+    )]
+    pub ai_agent: Account<'info, AiAgent>,
+    #[account(address = ai_agent.stake_mint)]
+    pub stake_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        constraint = operator_token_account.mint == ai_agent.stake_mint,
+        constraint = operator_token_account.owner == operator.key()
+    )]
+    pub operator_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"agent_stake", ai_agent.key().as_ref()],
+        bump
+    )]
+    pub stake_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAgent<'info> {
+    pub operator: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"agent", ai_agent.agent.as_ref()],
+        bump = ai_agent.bump,
+        constraint = ai_agent.operator == operator.key() @ EscrowError::UnauthorizedAgentOperator
+    )]
+    pub ai_agent: Account<'info, AiAgent>,
+}
+
+#[derive(Accounts)]
+pub struct SetAgentStatus<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.authority == authority.key() @ EscrowError::UnauthorizedAdmin
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        seeds = [b"agent", ai_agent.agent.as_ref()],
+        bump = ai_agent.bump
+    )]
+    pub ai_agent: Account<'info, AiAgent>,
+}
+
+#[derive(Accounts)]
+pub struct SlashAgent<'info> {
+    pub caller: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"dispute", dispute.escrow.as_ref(), &[dispute.milestone_index]],
+        bump = dispute.bump,
+        constraint = dispute.overturned_agents.contains(&ai_agent.agent) @ EscrowError::AgentNotSlashable
+    )]
+    pub dispute: Account<'info, Dispute>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        seeds = [b"agent", ai_agent.agent.as_ref()],
+        bump = ai_agent.bump
+    )]
+    pub ai_agent: Account<'info, AiAgent>,
+    #[account(address = ai_agent.stake_mint)]
+    pub stake_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"agent_stake", ai_agent.key().as_ref()],
+        bump
+    )]
+    pub stake_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = ai_agent.stake_mint,
+        associated_token::authority = config.treasury_owner,
+        associated_token::token_program = token_program
+    )]
+    pub protocol_treasury: Option<InterfaceAccount<'info, TokenAccount>>,
+    /// Paid on native SOL stakes
+    #[account(mut, address = config.treasury_owner)]
+    pub treasury_wallet: Option<SystemAccount<'info>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[event]
+pub struct AgentRegistered {
+    pub agent: Pubkey,
+    pub operator: Pubkey,
+    pub stake_mint: Pubkey,
+    pub stake_amount: u64,
+    pub model_id: [u8; 32],
+    pub endpoint_hash: [u8; 32],
+}
+
+#[event]
+pub struct AgentStakeChanged {
+    pub agent: Pubkey,
+    pub stake_amount: u64,
+}
+
+#[event]
+pub struct AgentMetadataUpdated {
+    pub agent: Pubkey,
+    pub model_id: [u8; 32],
+    pub endpoint_hash: [u8; 32],
+}
+
+#[event]
+pub struct AgentSigningKeyAdded {
+    pub agent: Pubkey,
+    pub key: Pubkey,
+    pub valid_from: i64,
+    pub valid_until: Option<i64>,
+}
+
+#[event]
+pub struct AgentSigningKeyRevoked {
+    pub agent: Pubkey,
+    pub key: Pubkey,
+    pub revoked_at: i64,
+}
+
+#[event]
+pub struct AgentStatusChanged {
+    pub agent: Pubkey,
+    pub status: AgentStatus,
+}
+
+#[event]
+pub struct AgentSlashed {
+    pub agent: Pubkey,
+    pub dispute: Pubkey,
+    pub amount: u64,
+    pub stake_amount: u64,
+    pub status: AgentStatus,
+}
+
+#[event]
+pub struct AgentConfigUpdated {
+    pub stake_mint: Pubkey,
+    pub min_stake: u64,
+    pub slash_bps: u16,
+    pub unbonding_period: i64,
+    pub require_registered_agent: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signing_key(valid_from: i64, valid_until: Option<i64>) -> SigningKey {
+        SigningKey {
+            key: Pubkey::new_unique(),
+            valid_from,
+            valid_until,
+        }
+    }
+
+    #[test]
+    fn signing_key_valid_from_start() {
+        let key = signing_key(100, None);
+        assert!(!key.is_valid_at(99));
+        assert!(key.is_valid_at(100));
+        assert!(key.is_valid_at(i64::MAX));
+    }
+
+    #[test]
+    fn signing_key_expires_at_valid_until() {
+        let key = signing_key(100, Some(200));
+        assert!(key.is_valid_at(199));
+        assert!(!key.is_valid_at(200));
+        assert!(!key.is_valid_at(201));
+    }
+
+    #[test]
+    fn revoked_signing_key_is_never_valid() {
+        let key = signing_key(100, Some(100));
+        assert!(!key.is_valid_at(99));
+        assert!(!key.is_valid_at(100));
+    }
+}
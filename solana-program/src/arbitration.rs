@@ -0,0 +1,962 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    close_vault, release_agent_disputes, settlement_destination, transfer_from_vault,
+    vault_balance, DisputeEvidence, DisputeOutcome, DisputeResolved, EscrowAccount, EscrowError,
+    EscrowStatus, MilestoneSettled, ProtocolConfig, VaultAccounts, BPS_DENOMINATOR,
+    MAX_VERIFIER_AGENTS,
+};
+
+/// Maximum number of arbitrators in the protocol-wide pool
+pub const MAX_ARBITRATORS: usize = 10;
+/// Maximum number of arbitrators seated on a single dispute panel
+pub const MAX_PANEL_SIZE: usize = 5;
+
+pub const DEFAULT_PANEL_SIZE: u8 = 3;
+pub const DEFAULT_QUORUM: u8 = 2;
+/// Share of votes cast the winning outcome needs (simple majority)
+pub const DEFAULT_VOTE_THRESHOLD_BPS: u16 = 5_001;
+
+/// How long a panel has to vote, until the authority configures other bounds
+pub const DEFAULT_DISPUTE_WINDOW: i64 = 48 * 60 * 60; // 48 hours in seconds
+pub const MIN_DISPUTE_WINDOW: i64 = 24 * 60 * 60; // 24 hours in seconds
+pub const MAX_DISPUTE_WINDOW: i64 = 14 * 24 * 60 * 60; // 14 days in seconds
+
+#[account]
+#[derive(InitSpace)]
+pub struct Dispute {
+    pub escrow: Pubkey,
+    pub milestone_index: u8,
+    pub raised_by: Pubkey,
+    /// Paid the rent for this account and its evidence trail, so gets it back on close
+    pub rent_payer: Pubkey,
+    #[max_len(MAX_PANEL_SIZE)]
+    pub panel: Vec<Pubkey>,
+    #[max_len(MAX_PANEL_SIZE)]
+    pub votes: Vec<ArbitratorVote>,
+    pub quorum: u8,
+    pub vote_threshold_bps: u16,
+    pub raised_at: i64,
+    pub deadline: i64,
+    /// Applied if the panel has not decided by the deadline
+    pub default_resolution: DefaultResolutionPolicy,
+    /// Posted by the raiser; held in the bond vault, or on this account for native SOL
+    pub bond_amount: u64,
+    pub bond_forfeit: BondForfeitDestination,
+    /// Agents whose verdicts the panel overturned, until their stakes are slashed
+    #[max_len(MAX_VERIFIER_AGENTS)]
+    pub overturned_agents: Vec<Pubkey>,
+    pub outcome: Option<DisputeOutcome>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct ArbitratorVote {
+    pub arbitrator: Pubkey,
+    pub outcome: DisputeOutcome,
+}
+
+/// How a dispute the panel failed to decide by its deadline is settled
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum DefaultResolutionPolicy {
+    RefundBuyer,
+    ReleaseToSeller,
+    /// Follow the milestone's AI verdict, splitting evenly if there is none
+    FollowAiVerdict,
+    Split { buyer_bps: u16 },
+}
+
+impl DefaultResolutionPolicy {
+    pub fn is_valid(&self) -> bool {
+        match self {
+            DefaultResolutionPolicy::Split { buyer_bps } => *buyer_bps as u64 <= BPS_DENOMINATOR,
+            _ => true,
+        }
+    }
+
+    /// The outcome this policy settles a lapsed dispute with
+    pub fn outcome(&self, verification_result: Option<bool>) -> DisputeOutcome {
+        match self {
+            DefaultResolutionPolicy::RefundBuyer => DisputeOutcome::FavorBuyer,
+            DefaultResolutionPolicy::ReleaseToSeller => DisputeOutcome::FavorSeller,
+            DefaultResolutionPolicy::FollowAiVerdict => match verification_result {
+                Some(true) => DisputeOutcome::FavorSeller,
+                Some(false) => DisputeOutcome::FavorBuyer,
+                None => DisputeOutcome::Split {
+                    buyer_bps: (BPS_DENOMINATOR / 2) as u16,
+                },
+            },
+            DefaultResolutionPolicy::Split { buyer_bps } => DisputeOutcome::Split {
+                buyer_bps: *buyer_bps,
+            },
+        }
+    }
+}
+
+/// Bond the raiser of a dispute posts, in base units of the escrow's mint
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum DisputeBond {
+    None,
+    Flat { amount: u64 },
+    /// Share of the escrow's total amount
+    Bps { bps: u16 },
+}
+
+impl DisputeBond {
+    pub fn is_valid(&self) -> bool {
+        match self {
+            DisputeBond::Bps { bps } => *bps as u64 <= BPS_DENOMINATOR,
+            _ => true,
+        }
+    }
+
+    /// Bond required to dispute an escrow of `escrow_amount`
+    pub fn amount(&self, escrow_amount: u64) -> Result<u64> {
+        match self {
+            DisputeBond::None => Ok(0),
+            DisputeBond::Flat { amount } => Ok(*amount),
+            DisputeBond::Bps { bps } => Ok((escrow_amount as u128)
+                .checked_mul(*bps as u128)
+                .ok_or(EscrowError::MathOverflow)?
+                .checked_div(BPS_DENOMINATOR as u128)
+                .ok_or(EscrowError::MathOverflow)? as u64),
+        }
+    }
+}
+
+/// Where a dispute bond goes when the raiser loses
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum BondForfeitDestination {
+    Counterparty,
+    /// Shared evenly by the arbitrators who voted
+    Arbitrators,
+    Treasury,
+}
+
+impl Dispute {
+    /// Whether `outcome` went the raiser's way. An even split counts for both parties.
+    pub fn raiser_prevailed(&self, outcome: &DisputeOutcome, buyer: Pubkey) -> bool {
+        let buyer_bps = match outcome {
+            DisputeOutcome::FavorBuyer => BPS_DENOMINATOR,
+            DisputeOutcome::FavorSeller => 0,
+            DisputeOutcome::Split { buyer_bps } => *buyer_bps as u64,
+        };
+        if self.raised_by == buyer {
+            buyer_bps * 2 >= BPS_DENOMINATOR
+        } else {
+            buyer_bps * 2 <= BPS_DENOMINATOR
+        }
+    }
+
+    /// The outcome backed by the configured share of votes cast, if any
+    pub fn winning_outcome(&self) -> Option<(DisputeOutcome, u8)> {
+        let mut best: Option<(&DisputeOutcome, u8)> = None;
+        for vote in &self.votes {
+            let count = self.votes.iter().filter(|v| v.outcome == vote.outcome).count() as u8;
+            let is_better = match best {
+                Some((_, best_count)) => count > best_count,
+                None => true,
+            };
+            if is_better {
+                best = Some((&vote.outcome, count));
+            }
+        }
+        
+        let (outcome, count) = best?;
+        let votes_cast = self.votes.len() as u64;
+        // count / votes_cast >= threshold, without rounding in either direction
+        let meets_threshold = (count as u64) * BPS_DENOMINATOR
+            >= votes_cast * self.vote_threshold_bps as u64;
+        
+        meets_threshold.then(|| (outcome.clone(), count))
+    }
+}
+
+/// Seat the next arbitrators from the protocol pool in round-robin order,
+/// skipping the escrow's own parties, and advance the pool cursor. Returns
+/// `None`, leaving the cursor alone, if the pool can't seat a quorum.
+pub fn select_panel(config: &mut ProtocolConfig, exclude: &[Pubkey]) -> Option<Vec<Pubkey>> {
+    let pool_len = config.arbitrators.len();
+    if pool_len == 0 {
+        return None;
+    }
+    
+    let start = config.arbitration_cursor as usize % pool_len;
+    let mut panel = Vec::with_capacity(config.panel_size as usize);
+    let mut considered = 0;
+    
+    while considered < pool_len && panel.len() < config.panel_size as usize {
+        let arbitrator = config.arbitrators[(start + considered) % pool_len];
+        if !exclude.contains(&arbitrator) {
+            panel.push(arbitrator);
+        }
+        considered += 1;
+    }
+    
+    if panel.len() < config.quorum as usize {
+        return None;
+    }
+    
+    config.arbitration_cursor = ((start + considered) % pool_len) as u32;
+    Some(panel)
+}
+
+/// Record a panel member's vote on the dispute outcome
+pub fn cast_dispute_vote(
+    ctx: Context<CastDisputeVote>,
+    outcome: DisputeOutcome,
+) -> Result<()> {
+    let dispute = &mut ctx.accounts.dispute;
+    let arbitrator = ctx.accounts.arbitrator.key();
+    
+    require!(dispute.outcome.is_none(), EscrowError::DisputeAlreadyFinalized);
+    require!(dispute.panel.contains(&arbitrator), EscrowError::NotOnPanel);
+    require!(
+        !dispute.votes.iter().any(|v| v.arbitrator == arbitrator),
+        EscrowError::AlreadyVoted
+    );
+    if let DisputeOutcome::Split { buyer_bps } = outcome {
+        require!(buyer_bps as u64 <= BPS_DENOMINATOR, EscrowError::InvalidSplit);
+    }
+    
+    let clock = Clock::get()?;
+    require!(clock.unix_timestamp <= dispute.deadline, EscrowError::DisputeVotingClosed);
+    
+    dispute.votes.push(ArbitratorVote {
+        arbitrator,
+        outcome: outcome.clone(),
+    });
+    
+    emit!(DisputeVoteCast {
+        escrow_id: ctx.accounts.escrow.escrow_id,
+        arbitrator,
+        outcome,
+        votes_cast: dispute.votes.len() as u8,
+    });
+    
+    Ok(())
+}
+
+/// Finalize a dispute once quorum is reached or voting has closed, and settle
+/// the vault according to the winning outcome. A dispute the panel failed to
+/// decide by its deadline falls back to its default resolution policy.
+/// Anyone can crank this.
+pub fn finalize_dispute<'info>(
+    ctx: Context<'_, '_, 'info, 'info, FinalizeDispute<'info>>,
+) -> Result<()> {
+    let escrow_account_info = ctx.accounts.escrow.to_account_info();
+    let dispute_account_info = ctx.accounts.dispute.to_account_info();
+    let is_native = ctx.accounts.escrow.is_native();
+    let bond_amount = ctx.accounts.dispute.bond_amount;
+    
+    // A bond forfeited to arbitrators is paid to the voters' accounts, which
+    // lead the remaining accounts in vote order. The registry entries of the
+    // agents the dispute holds follow in verdict order, then any hook accounts.
+    let arbitrator_count = if bond_amount > 0
+        && ctx.accounts.dispute.bond_forfeit == BondForfeitDestination::Arbitrators
+    {
+        ctx.accounts.dispute.votes.len()
+    } else {
+        0
+    };
+    let contested_agents = ctx.accounts.escrow.registered_verdict_agents();
+    require!(
+        ctx.remaining_accounts.len() >= arbitrator_count + contested_agents.len(),
+        EscrowError::MissingSettlementAccount
+    );
+    let (arbitrator_accounts, agent_and_hook_accounts) =
+        ctx.remaining_accounts.split_at(arbitrator_count);
+    let (agent_entries, hook_accounts) = agent_and_hook_accounts.split_at(contested_agents.len());
+    
+    let vault = VaultAccounts::resolve(
+        is_native,
+        ctx.accounts.token_program.as_ref(),
+        ctx.accounts.escrow_vault.as_ref(),
+        ctx.accounts.token_mint.as_ref(),
+        hook_accounts,
+    )?;
+    let bond_vault = if bond_amount > 0 || ctx.accounts.bond_vault.is_some() {
+        VaultAccounts::resolve(
+            is_native,
+            ctx.accounts.token_program.as_ref(),
+            ctx.accounts.bond_vault.as_ref(),
+            ctx.accounts.token_mint.as_ref(),
+            hook_accounts,
+        )?
+    } else {
+        None
+    };
+    // Pay out whatever the bond vault holds, so tokens sent to it after the
+    // bond was posted can't stop it from closing
+    let bond_payout = match bond_vault.as_ref() {
+        Some(bond_vault) => vault_balance(&bond_vault.escrow_vault.to_account_info())?,
+        None => bond_amount,
+    };
+    let buyer_destination = settlement_destination(
+        is_native,
+        ctx.accounts.buyer_token_account.as_ref(),
+        ctx.accounts.buyer_wallet.as_ref(),
+    )?;
+    let seller_destination = settlement_destination(
+        is_native,
+        ctx.accounts.seller_token_account.as_ref(),
+        ctx.accounts.seller_wallet.as_ref(),
+    )?;
+    let treasury_destination = settlement_destination(
+        is_native,
+        ctx.accounts.protocol_treasury.as_ref(),
+        ctx.accounts.treasury_wallet.as_ref(),
+    )?;
+    let dispute = &mut ctx.accounts.dispute;
+    let escrow = &mut ctx.accounts.escrow;
+    
+    require!(escrow.status == EscrowStatus::Disputed, EscrowError::InvalidEscrowState);
+    require!(dispute.outcome.is_none(), EscrowError::DisputeAlreadyFinalized);
+    
+    let clock = Clock::get()?;
+    let quorum_reached = dispute.votes.len() >= dispute.quorum as usize;
+    let decision = if quorum_reached {
+        dispute.winning_outcome()
+    } else {
+        None
+    };
+    
+    let decided_by_panel = decision.is_some();
+    let (outcome, votes_for) = match decision {
+        Some(decision) => decision,
+        None => {
+            require!(
+                clock.unix_timestamp > dispute.deadline,
+                if quorum_reached {
+                    EscrowError::DisputeUndecided
+                } else {
+                    EscrowError::QuorumNotReached
+                }
+            );
+            
+            let outcome = dispute.default_resolution.outcome(escrow.verification_result);
+            emit!(DisputeDefaultApplied {
+                escrow_id: escrow.escrow_id,
+                milestone_index: dispute.milestone_index,
+                policy: dispute.default_resolution.clone(),
+                outcome: outcome.clone(),
+            });
+            (outcome, 0)
+        },
+    };
+    
+    let (buyer_amount, seller_amount, fee_amount) = escrow.settlement_amounts(&outcome)?;
+    
+    // Store immutable values to avoid borrow conflicts
+    let escrow_id = escrow.escrow_id;
+    let bump = escrow.bump;
+    
+    let seeds = &[
+        b"escrow",
+        escrow_id.as_ref(),
+        &[bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    transfer_from_vault(
+        vault.as_ref(),
+        buyer_destination.clone(),
+        escrow_account_info.clone(),
+        signer,
+        buyer_amount,
+    )?;
+    transfer_from_vault(
+        vault.as_ref(),
+        seller_destination.clone(),
+        escrow_account_info.clone(),
+        signer,
+        seller_amount,
+    )?;
+    transfer_from_vault(
+        vault.as_ref(),
+        treasury_destination.clone(),
+        escrow_account_info.clone(),
+        signer,
+        fee_amount,
+    )?;
+    
+    // The bond goes back to a raiser who prevailed and is forfeited otherwise.
+    // Native SOL bonds sit on the dispute account itself.
+    let mut forfeited_to = None;
+    if bond_payout > 0 {
+        let bond_authority = if is_native {
+            dispute_account_info
+        } else {
+            escrow_account_info.clone()
+        };
+        let (raiser_destination, counterparty_destination) = if dispute.raised_by == escrow.buyer {
+            (buyer_destination, seller_destination)
+        } else {
+            (seller_destination, buyer_destination)
+        };
+        if dispute.raiser_prevailed(&outcome, escrow.buyer) {
+            transfer_from_vault(
+                bond_vault.as_ref(),
+                raiser_destination,
+                bond_authority,
+                signer,
+                bond_payout,
+            )?;
+        } else {
+            match dispute.bond_forfeit {
+                BondForfeitDestination::Counterparty => transfer_from_vault(
+                    bond_vault.as_ref(),
+                    counterparty_destination,
+                    bond_authority,
+                    signer,
+                    bond_payout,
+                )?,
+                BondForfeitDestination::Arbitrators => pay_arbitrators(
+                    bond_vault.as_ref(),
+                    arbitrator_accounts,
+                    &dispute.votes,
+                    treasury_destination,
+                    bond_authority,
+                    signer,
+                    bond_payout,
+                )?,
+                BondForfeitDestination::Treasury => transfer_from_vault(
+                    bond_vault.as_ref(),
+                    treasury_destination,
+                    bond_authority,
+                    signer,
+                    bond_payout,
+                )?,
+            }
+            forfeited_to = Some(dispute.bond_forfeit);
+        }
+    }
+    
+    // Return the emptied bond vault's rent to the raiser
+    if let (Some(vault), Some(bond_vault)) = (vault.as_ref(), ctx.accounts.bond_vault.as_ref()) {
+        let raiser = ctx.accounts
+            .raiser
+            .as_ref()
+            .ok_or(EscrowError::MissingSettlementAccount)?;
+        close_vault(
+            vault.token_program,
+            bond_vault,
+            vault.token_mint,
+            raiser.to_account_info(),
+            escrow_account_info,
+            signer,
+        )?;
+    }
+    
+    // Registered agents the panel decided against are exposed to slashing
+    let overturned_result = match outcome {
+        DisputeOutcome::FavorBuyer => Some(true),
+        DisputeOutcome::FavorSeller => Some(false),
+        DisputeOutcome::Split { .. } => None,
+    };
+    if let (true, Some(overturned_result)) = (decided_by_panel, overturned_result) {
+        dispute.overturned_agents = escrow
+            .agent_verdicts
+            .iter()
+            .filter(|verdict| verdict.registered && verdict.result == overturned_result)
+            .map(|verdict| verdict.agent)
+            .collect();
+    }
+    // Overturned agents stay held until slashed
+    release_agent_disputes(&contested_agents, agent_entries, &dispute.overturned_agents)?;
+    
+    match outcome {
+        DisputeOutcome::FavorBuyer => {
+            escrow.verification_result = Some(false);
+        },
+        DisputeOutcome::FavorSeller => {
+            escrow.verification_result = Some(true);
+        },
+        DisputeOutcome::Split { .. } => {},
+    }
+    
+    let milestone_index = dispute.milestone_index;
+    dispute.outcome = Some(outcome.clone());
+    escrow.dispute_raised = false;
+    escrow.settle_current_milestone(EscrowStatus::Resolved)?;
+    
+    emit!(MilestoneSettled {
+        escrow_id,
+        milestone_index,
+        status: EscrowStatus::Resolved,
+        buyer_amount,
+        seller_amount,
+        fee_amount,
+    });
+    if bond_payout > 0 {
+        emit!(DisputeBondSettled {
+            escrow_id,
+            milestone_index,
+            raised_by: dispute.raised_by,
+            amount: bond_payout,
+            forfeited_to,
+        });
+    }
+    emit!(DisputeResolved {
+        escrow_id,
+        milestone_index,
+        outcome,
+        votes_for,
+        votes_cast: dispute.votes.len() as u8,
+        buyer_amount,
+        seller_amount,
+        fee_amount,
+    });
+    
+    Ok(())
+}
+
+/// Close a finalized dispute and its evidence trail, returning their rent to
+/// whoever paid it. Waits until every overturned agent has been slashed.
+pub fn close_dispute(ctx: Context<CloseDispute>) -> Result<()> {
+    let escrow = &mut ctx.accounts.escrow;
+    escrow.open_disputes = escrow
+        .open_disputes
+        .checked_sub(1)
+        .ok_or(EscrowError::MathOverflow)?;
+    
+    emit!(DisputeClosed {
+        escrow_id: escrow.escrow_id,
+        milestone_index: ctx.accounts.dispute.milestone_index,
+    });
+    
+    Ok(())
+}
+
+/// Open a dispute over the escrow's current milestone: seat a panel, start the
+/// voting and evidence clocks and freeze the escrow. Callers post any bond and
+/// set the account bumps.
+pub(crate) fn open_dispute(
+    escrow: &mut Account<EscrowAccount>,
+    config: &mut ProtocolConfig,
+    dispute: &mut Account<Dispute>,
+    evidence: &mut DisputeEvidence,
+    raised_by: Pubkey,
+    rent_payer: Pubkey,
+    now: i64,
+) -> Result<()> {
+    let deadline = now
+        .checked_add(escrow.dispute_window)
+        .ok_or(EscrowError::MathOverflow)?;
+    
+    // Parties to the escrow never sit on their own panel. A party must wait for
+    // enough arbitrators, but an escalation the escrow raises itself can't be
+    // retried, so it proceeds without a panel and settles by the default
+    // resolution policy at the deadline.
+    let panel = match select_panel(config, &[escrow.buyer, escrow.seller]) {
+        Some(panel) => panel,
+        None => {
+            require_keys_eq!(raised_by, escrow.key(), EscrowError::NotEnoughArbitrators);
+            Vec::new()
+        },
+    };
+    
+    dispute.escrow = escrow.key();
+    dispute.milestone_index = escrow.current_milestone;
+    dispute.raised_by = raised_by;
+    dispute.rent_payer = rent_payer;
+    dispute.panel = panel;
+    dispute.votes = Vec::new();
+    dispute.quorum = config.quorum;
+    dispute.vote_threshold_bps = config.vote_threshold_bps;
+    dispute.default_resolution = config.default_resolution.clone();
+    dispute.bond_amount = 0;
+    dispute.bond_forfeit = config.bond_forfeit;
+    dispute.overturned_agents = Vec::new();
+    dispute.raised_at = now;
+    dispute.deadline = deadline;
+    dispute.outcome = None;
+    
+    evidence.dispute = dispute.key();
+    evidence.deadline = now
+        .checked_add(escrow.evidence_window)
+        .ok_or(EscrowError::MathOverflow)?;
+    evidence.entries = Vec::new();
+    
+    escrow.dispute_raised = true;
+    escrow.dispute_deadline = Some(deadline);
+    escrow.open_disputes = escrow
+        .open_disputes
+        .checked_add(1)
+        .ok_or(EscrowError::MathOverflow)?;
+    escrow.set_status(EscrowStatus::Disputed);
+    
+    Ok(())
+}
+
+/// Share a forfeited bond evenly among the arbitrators who voted, paying the
+/// accounts passed in vote order: wallets for native SOL, token accounts
+/// otherwise. The remainder of an uneven split, or the whole bond if nobody
+/// voted, goes to the treasury.
+fn pay_arbitrators<'info>(
+    bond_vault: Option<&VaultAccounts<'_, 'info>>,
+    arbitrator_accounts: &'info [AccountInfo<'info>],
+    votes: &[ArbitratorVote],
+    treasury: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    let share = amount.checked_div(votes.len() as u64).unwrap_or(0);
+    for (vote, account) in votes.iter().zip(arbitrator_accounts) {
+        let is_arbitrator_account = match bond_vault {
+            Some(bond_vault) => {
+                let token_account = InterfaceAccount::<TokenAccount>::try_from(account)?;
+                token_account.owner == vote.arbitrator
+                    && token_account.mint == bond_vault.token_mint.key()
+            },
+            None => account.key() == vote.arbitrator,
+        };
+        require!(is_arbitrator_account, EscrowError::InvalidPayoutAccount);
+        
+        transfer_from_vault(bond_vault, account.clone(), authority.clone(), signer, share)?;
+    }
+    
+    let remainder = (votes.len() as u64)
+        .checked_mul(share)
+        .and_then(|paid| amount.checked_sub(paid))
+        .ok_or(EscrowError::MathOverflow)?;
+    transfer_from_vault(bond_vault, treasury, authority, signer, remainder)
+}
+
+#[derive(Accounts)]
+pub struct CastDisputeVote<'info> {
+    pub arbitrator: Signer<'info>,
+    #[account(
+        seeds = [b"escrow", escrow.escrow_id.as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        seeds = [b"dispute", escrow.key().as_ref(), &[escrow.current_milestone]],
+        bump = dispute.bump
+    )]
+    pub dispute: Account<'info, Dispute>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeDispute<'info> {
+    pub caller: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.escrow_id.as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        seeds = [b"dispute", escrow.key().as_ref(), &[escrow.current_milestone]],
+        bump = dispute.bump
+    )]
+    pub dispute: Account<'info, Dispute>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump
+    )]
+    pub escrow_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = escrow.token_mint)]
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        constraint = buyer_token_account.mint == escrow.token_mint,
+        constraint = buyer_token_account.owner == escrow.buyer
+    )]
+    pub buyer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = seller_token_account.mint == escrow.token_mint,
+        constraint = escrow.is_seller_payout_account(
+            seller_token_account.key(),
+            seller_token_account.owner
+        ) @ EscrowError::InvalidPayoutAccount
+    )]
+    pub seller_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = escrow.token_mint,
+        associated_token::authority = config.treasury_owner,
+        associated_token::token_program = token_program
+    )]
+    pub protocol_treasury: Option<InterfaceAccount<'info, TokenAccount>>,
+    /// Wallets paid on native SOL escrows
+    #[account(mut, address = escrow.buyer)]
+    pub buyer_wallet: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        address = escrow.seller_payout.unwrap_or(escrow.seller) @ EscrowError::InvalidPayoutAccount
+    )]
+    pub seller_wallet: Option<SystemAccount<'info>>,
+    #[account(mut, address = config.treasury_owner)]
+    pub treasury_wallet: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        seeds = [b"bond", dispute.key().as_ref()],
+        bump
+    )]
+    pub bond_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    /// Gets the bond vault's rent back
+    #[account(mut, address = dispute.raised_by)]
+    pub raiser: Option<SystemAccount<'info>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDispute<'info> {
+    pub caller: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.escrow_id.as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+    #[account(
+        mut,
+        seeds = [b"dispute", escrow.key().as_ref(), &[dispute.milestone_index]],
+        bump = dispute.bump,
+        constraint = dispute.outcome.is_some() && dispute.overturned_agents.is_empty()
+            @ EscrowError::DisputeNotClosable,
+        close = rent_payer
+    )]
+    pub dispute: Account<'info, Dispute>,
+    #[account(
+        mut,
+        seeds = [b"evidence", dispute.key().as_ref()],
+        bump = evidence.bump,
+        close = rent_payer
+    )]
+    pub evidence: Account<'info, DisputeEvidence>,
+    #[account(mut, address = dispute.rent_payer)]
+    pub rent_payer: SystemAccount<'info>,
+}
+
+#[event]
+pub struct DisputePanelSelected {
+    pub escrow_id: [u8; 32],
+    pub milestone_index: u8,
+    pub raised_by: Pubkey,
+    pub panel: Vec<Pubkey>,
+    pub deadline: i64,
+    pub bond_amount: u64,
+}
+
+#[event]
+pub struct DisputeVoteCast {
+    pub escrow_id: [u8; 32],
+    pub arbitrator: Pubkey,
+    pub outcome: DisputeOutcome,
+    pub votes_cast: u8,
+}
+
+#[event]
+pub struct DisputeDefaultApplied {
+    pub escrow_id: [u8; 32],
+    pub milestone_index: u8,
+    pub policy: DefaultResolutionPolicy,
+    pub outcome: DisputeOutcome,
+}
+
+#[event]
+pub struct DefaultResolutionUpdated {
+    pub policy: DefaultResolutionPolicy,
+}
+
+#[event]
+pub struct DisputeBondSettled {
+    pub escrow_id: [u8; 32],
+    pub milestone_index: u8,
+    pub raised_by: Pubkey,
+    pub amount: u64,
+    /// `None` when the bond was returned to the raiser
+    pub forfeited_to: Option<BondForfeitDestination>,
+}
+
+#[event]
+pub struct DisputeBondUpdated {
+    pub bond: DisputeBond,
+    pub forfeit: BondForfeitDestination,
+}
+
+#[event]
+pub struct DisputeClosed {
+    pub escrow_id: [u8; 32],
+    pub milestone_index: u8,
+}
+
+#[event]
+pub struct ArbitrationConfigUpdated {
+    pub panel_size: u8,
+    pub quorum: u8,
+    pub vote_threshold_bps: u16,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dispute(raised_by: Pubkey, votes: &[DisputeOutcome]) -> Dispute {
+        Dispute {
+            escrow: Pubkey::new_unique(),
+            milestone_index: 0,
+            raised_by,
+            rent_payer: raised_by,
+            panel: Vec::new(),
+            votes: votes
+                .iter()
+                .map(|outcome| ArbitratorVote {
+                    arbitrator: Pubkey::new_unique(),
+                    outcome: outcome.clone(),
+                })
+                .collect(),
+            quorum: DEFAULT_QUORUM,
+            vote_threshold_bps: DEFAULT_VOTE_THRESHOLD_BPS,
+            raised_at: 0,
+            deadline: 0,
+            default_resolution: DefaultResolutionPolicy::FollowAiVerdict,
+            bond_amount: 0,
+            bond_forfeit: BondForfeitDestination::Counterparty,
+            overturned_agents: Vec::new(),
+            outcome: None,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn winning_outcome_needs_a_vote() {
+        assert_eq!(dispute(Pubkey::default(), &[]).winning_outcome(), None);
+    }
+
+    #[test]
+    fn winning_outcome_takes_majority() {
+        let dispute = dispute(
+            Pubkey::default(),
+            &[
+                DisputeOutcome::FavorBuyer,
+                DisputeOutcome::FavorSeller,
+                DisputeOutcome::FavorBuyer,
+            ],
+        );
+        assert_eq!(dispute.winning_outcome(), Some((DisputeOutcome::FavorBuyer, 2)));
+    }
+
+    #[test]
+    fn winning_outcome_leaves_tie_undecided() {
+        let dispute = dispute(
+            Pubkey::default(),
+            &[DisputeOutcome::FavorBuyer, DisputeOutcome::FavorSeller],
+        );
+        assert_eq!(dispute.winning_outcome(), None);
+    }
+
+    #[test]
+    fn winning_outcome_counts_splits_by_ratio() {
+        let dispute = dispute(
+            Pubkey::default(),
+            &[
+                DisputeOutcome::Split { buyer_bps: 5_000 },
+                DisputeOutcome::Split { buyer_bps: 4_000 },
+                DisputeOutcome::Split { buyer_bps: 5_000 },
+            ],
+        );
+        assert_eq!(
+            dispute.winning_outcome(),
+            Some((DisputeOutcome::Split { buyer_bps: 5_000 }, 2))
+        );
+    }
+
+    #[test]
+    fn winning_outcome_applies_supermajority_threshold() {
+        let mut dispute = dispute(
+            Pubkey::default(),
+            &[
+                DisputeOutcome::FavorSeller,
+                DisputeOutcome::FavorSeller,
+                DisputeOutcome::FavorBuyer,
+            ],
+        );
+        dispute.vote_threshold_bps = 6_667;
+        assert_eq!(dispute.winning_outcome(), None);
+        dispute.vote_threshold_bps = 6_666;
+        assert_eq!(dispute.winning_outcome(), Some((DisputeOutcome::FavorSeller, 2)));
+    }
+
+    #[test]
+    fn default_resolution_outcomes() {
+        assert_eq!(
+            DefaultResolutionPolicy::RefundBuyer.outcome(Some(true)),
+            DisputeOutcome::FavorBuyer
+        );
+        assert_eq!(
+            DefaultResolutionPolicy::ReleaseToSeller.outcome(Some(false)),
+            DisputeOutcome::FavorSeller
+        );
+        assert_eq!(
+            DefaultResolutionPolicy::Split { buyer_bps: 3_000 }.outcome(None),
+            DisputeOutcome::Split { buyer_bps: 3_000 }
+        );
+    }
+
+    #[test]
+    fn default_resolution_follows_ai_verdict() {
+        let policy = DefaultResolutionPolicy::FollowAiVerdict;
+        assert_eq!(policy.outcome(Some(true)), DisputeOutcome::FavorSeller);
+        assert_eq!(policy.outcome(Some(false)), DisputeOutcome::FavorBuyer);
+        assert_eq!(policy.outcome(None), DisputeOutcome::Split { buyer_bps: 5_000 });
+    }
+
+    #[test]
+    fn raiser_prevailed_for_buyer() {
+        let buyer = Pubkey::new_unique();
+        let dispute = dispute(buyer, &[]);
+        assert!(dispute.raiser_prevailed(&DisputeOutcome::FavorBuyer, buyer));
+        assert!(!dispute.raiser_prevailed(&DisputeOutcome::FavorSeller, buyer));
+        assert!(dispute.raiser_prevailed(&DisputeOutcome::Split { buyer_bps: 6_000 }, buyer));
+        assert!(!dispute.raiser_prevailed(&DisputeOutcome::Split { buyer_bps: 4_000 }, buyer));
+    }
+
+    #[test]
+    fn raiser_prevailed_for_seller() {
+        let buyer = Pubkey::new_unique();
+        let dispute = dispute(Pubkey::new_unique(), &[]);
+        assert!(dispute.raiser_prevailed(&DisputeOutcome::FavorSeller, buyer));
+        assert!(!dispute.raiser_prevailed(&DisputeOutcome::FavorBuyer, buyer));
+        assert!(dispute.raiser_prevailed(&DisputeOutcome::Split { buyer_bps: 4_000 }, buyer));
+        assert!(!dispute.raiser_prevailed(&DisputeOutcome::Split { buyer_bps: 6_000 }, buyer));
+    }
+
+    #[test]
+    fn raiser_prevailed_on_even_split_either_way() {
+        let buyer = Pubkey::new_unique();
+        let even = DisputeOutcome::Split { buyer_bps: 5_000 };
+        assert!(dispute(buyer, &[]).raiser_prevailed(&even, buyer));
+        assert!(dispute(Pubkey::new_unique(), &[]).raiser_prevailed(&even, buyer));
+    }
+
+    #[test]
+    fn dispute_bond_amounts() {
+        assert_eq!(DisputeBond::None.amount(10_000).unwrap(), 0);
+        assert_eq!(DisputeBond::Flat { amount: 250 }.amount(10_000).unwrap(), 250);
+        assert_eq!(DisputeBond::Bps { bps: 500 }.amount(10_000).unwrap(), 500);
+        assert_eq!(DisputeBond::Bps { bps: 500 }.amount(19).unwrap(), 0);
+        assert_eq!(DisputeBond::Bps { bps: 10_000 }.amount(u64::MAX).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn dispute_bond_validity() {
+        assert!(DisputeBond::Bps { bps: 10_000 }.is_valid());
+        assert!(!DisputeBond::Bps { bps: 10_001 }.is_valid());
+        assert!(DisputeBond::Flat { amount: u64::MAX }.is_valid());
+    }
+}
@@ -0,0 +1,204 @@
+use anchor_lang::prelude::*;
+use solana_instructions_sysvar::get_instruction_relative;
+use solana_sdk_ids::ed25519_program;
+
+use crate::EscrowError;
+
+/// Ed25519 program instruction layout (see solana-ed25519-program):
+/// num_signatures (u8) + padding (u8), followed by one 14-byte offsets
+/// block per signature, followed by the signature, pubkey and message data.
+const SIGNATURE_OFFSETS_START: usize = 2;
+const SIGNATURE_OFFSETS_SERIALIZED_SIZE: usize = 14;
+const SIGNATURE_SERIALIZED_SIZE: usize = 64;
+const PUBKEY_SERIALIZED_SIZE: usize = 32;
+
+/// Instruction index the Ed25519 program uses to mean "this instruction"
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Verify that the instruction immediately preceding the current one is an
+/// Ed25519 program instruction checking `signature` by `pubkey` over exactly
+/// `message`. The native program has already verified the signature itself
+/// by the time we run, so all that is left is to bind its inputs to ours.
+pub fn verify_ed25519_instruction(
+    instructions_sysvar: &AccountInfo,
+    pubkey: &Pubkey,
+    message: &[u8],
+    signature: &[u8; 64],
+) -> Result<()> {
+    let ix = get_instruction_relative(-1, instructions_sysvar)
+        .map_err(|_| error!(EscrowError::InvalidSignature))?;
+    
+    require_keys_eq!(ix.program_id, ed25519_program::ID, EscrowError::InvalidSignature);
+    require!(ix.accounts.is_empty(), EscrowError::InvalidSignature);
+    
+    check_ed25519_data(&ix.data, pubkey, message, signature)
+}
+
+/// Check that Ed25519 program instruction `data` carries exactly one
+/// signature, with every offset pointing into `data` itself, and that it
+/// signs `message` by `pubkey` with `signature`.
+fn check_ed25519_data(
+    data: &[u8],
+    pubkey: &Pubkey,
+    message: &[u8],
+    signature: &[u8; 64],
+) -> Result<()> {
+    require!(
+        data.len() >= SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_SERIALIZED_SIZE,
+        EscrowError::InvalidSignature
+    );
+    // Exactly one signature, so nothing else can ride along in the same proof
+    require!(data[0] == 1, EscrowError::InvalidSignature);
+    
+    let offsets = &data[SIGNATURE_OFFSETS_START..SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_SERIALIZED_SIZE];
+    let read_u16 = |at: usize| u16::from_le_bytes([offsets[at], offsets[at + 1]]);
+    
+    let signature_offset = read_u16(0) as usize;
+    let signature_instruction_index = read_u16(2);
+    let public_key_offset = read_u16(4) as usize;
+    let public_key_instruction_index = read_u16(6);
+    let message_data_offset = read_u16(8) as usize;
+    let message_data_size = read_u16(10) as usize;
+    let message_instruction_index = read_u16(12);
+    
+    // All data must live inside the Ed25519 instruction itself, otherwise the
+    // signed bytes could be pulled from an instruction we never inspect
+    require!(
+        signature_instruction_index == CURRENT_INSTRUCTION
+            && public_key_instruction_index == CURRENT_INSTRUCTION
+            && message_instruction_index == CURRENT_INSTRUCTION,
+        EscrowError::InvalidSignature
+    );
+    
+    let signed_pubkey = slice_at(data, public_key_offset, PUBKEY_SERIALIZED_SIZE)?;
+    let signed_signature = slice_at(data, signature_offset, SIGNATURE_SERIALIZED_SIZE)?;
+    let signed_message = slice_at(data, message_data_offset, message_data_size)?;
+    
+    require!(signed_pubkey == pubkey.as_ref(), EscrowError::InvalidSignature);
+    require!(signed_signature == signature.as_ref(), EscrowError::InvalidSignature);
+    require!(signed_message == message, EscrowError::InvalidSignature);
+    
+    Ok(())
+}
+
+fn slice_at(data: &[u8], offset: usize, len: usize) -> Result<&[u8]> {
+    let end = offset
+        .checked_add(len)
+        .ok_or(EscrowError::InvalidSignature)?;
+    data.get(offset..end)
+        .ok_or_else(|| error!(EscrowError::InvalidSignature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MESSAGE: &[u8] = b"aetherlock verdict";
+
+    /// Ed25519 instruction data laid out the way the native program's
+    /// `new_ed25519_instruction` builds it: offsets, pubkey, signature, message
+    fn instruction_data(pubkey: &Pubkey, signature: &[u8; 64], message: &[u8]) -> Vec<u8> {
+        let public_key_offset = SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_SERIALIZED_SIZE;
+        let signature_offset = public_key_offset + PUBKEY_SERIALIZED_SIZE;
+        let message_data_offset = signature_offset + SIGNATURE_SERIALIZED_SIZE;
+        
+        let mut data = vec![1, 0];
+        for value in [
+            signature_offset as u16,
+            CURRENT_INSTRUCTION,
+            public_key_offset as u16,
+            CURRENT_INSTRUCTION,
+            message_data_offset as u16,
+            message.len() as u16,
+            CURRENT_INSTRUCTION,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(pubkey.as_ref());
+        data.extend_from_slice(signature);
+        data.extend_from_slice(message);
+        data
+    }
+
+    fn set_u16(data: &mut [u8], field: usize, value: u16) {
+        let at = SIGNATURE_OFFSETS_START + field * 2;
+        data[at..at + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn accepts_matching_instruction() {
+        let pubkey = Pubkey::new_unique();
+        let data = instruction_data(&pubkey, &[7; 64], MESSAGE);
+        assert!(check_ed25519_data(&data, &pubkey, MESSAGE, &[7; 64]).is_ok());
+    }
+
+    #[test]
+    fn rejects_wrong_pubkey_signature_or_message() {
+        let pubkey = Pubkey::new_unique();
+        let data = instruction_data(&pubkey, &[7; 64], MESSAGE);
+        
+        assert!(check_ed25519_data(&data, &Pubkey::new_unique(), MESSAGE, &[7; 64]).is_err());
+        assert!(check_ed25519_data(&data, &pubkey, MESSAGE, &[8; 64]).is_err());
+        assert!(check_ed25519_data(&data, &pubkey, b"aetherlock verdicT", &[7; 64]).is_err());
+        assert!(check_ed25519_data(&data, &pubkey, b"aetherlock", &[7; 64]).is_err());
+    }
+
+    #[test]
+    fn rejects_other_signature_counts() {
+        let pubkey = Pubkey::new_unique();
+        let mut data = instruction_data(&pubkey, &[7; 64], MESSAGE);
+        
+        data[0] = 0;
+        assert!(check_ed25519_data(&data, &pubkey, MESSAGE, &[7; 64]).is_err());
+        data[0] = 2;
+        assert!(check_ed25519_data(&data, &pubkey, MESSAGE, &[7; 64]).is_err());
+    }
+
+    #[test]
+    fn rejects_data_from_other_instructions() {
+        let pubkey = Pubkey::new_unique();
+        // Signature, pubkey and message instruction indices
+        for field in [1, 3, 6] {
+            let mut data = instruction_data(&pubkey, &[7; 64], MESSAGE);
+            set_u16(&mut data, field, 0);
+            assert!(check_ed25519_data(&data, &pubkey, MESSAGE, &[7; 64]).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_out_of_bounds_offsets() {
+        let pubkey = Pubkey::new_unique();
+        let data = instruction_data(&pubkey, &[7; 64], MESSAGE);
+        let len = data.len() as u16;
+        
+        // Signature, pubkey and message offsets just past the end
+        for (field, size) in [(0, 64), (2, 32), (4, MESSAGE.len() as u16)] {
+            let mut data = data.clone();
+            set_u16(&mut data, field, len - size + 1);
+            assert!(check_ed25519_data(&data, &pubkey, MESSAGE, &[7; 64]).is_err());
+        }
+        
+        let mut data = data.clone();
+        set_u16(&mut data, 5, MESSAGE.len() as u16 + 1);
+        assert!(check_ed25519_data(&data, &pubkey, MESSAGE, &[7; 64]).is_err());
+    }
+
+    #[test]
+    fn rejects_overflowing_offsets() {
+        let pubkey = Pubkey::new_unique();
+        let mut data = instruction_data(&pubkey, &[7; 64], MESSAGE);
+        set_u16(&mut data, 4, u16::MAX);
+        set_u16(&mut data, 5, u16::MAX);
+        assert!(check_ed25519_data(&data, &pubkey, MESSAGE, &[7; 64]).is_err());
+        
+        assert!(slice_at(&[0; 4], usize::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_header() {
+        let pubkey = Pubkey::new_unique();
+        let data = instruction_data(&pubkey, &[7; 64], MESSAGE);
+        let header = SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_SERIALIZED_SIZE;
+        assert!(check_ed25519_data(&data[..header - 1], &pubkey, MESSAGE, &[7; 64]).is_err());
+    }
+}
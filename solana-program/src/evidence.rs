@@ -0,0 +1,205 @@
+use anchor_lang::prelude::*;
+
+use crate::{Dispute, EscrowAccount, EscrowError, MAX_VERIFIER_AGENTS};
+
+/// Maximum number of entries each submitter may add to a dispute's evidence trail,
+/// so one party cannot crowd the other out
+pub const MAX_EVIDENCE_ENTRIES_PER_SUBMITTER: usize = 8;
+/// Maximum number of entries in a dispute's evidence trail: both parties' entries
+/// plus one report from each agent when a split verdict escalates
+pub const MAX_EVIDENCE_ENTRIES: usize = 2 * MAX_EVIDENCE_ENTRIES_PER_SUBMITTER + MAX_VERIFIER_AGENTS;
+/// Maximum length of an evidence URI (IPFS/Arweave/https)
+pub const MAX_EVIDENCE_URI_LEN: usize = 128;
+/// How long after a dispute is raised the parties may keep adding evidence,
+/// until the authority configures other bounds
+pub const DEFAULT_EVIDENCE_WINDOW: i64 = 24 * 60 * 60; // 24 hours in seconds
+pub const MIN_EVIDENCE_WINDOW: i64 = 60 * 60; // 1 hour in seconds
+pub const MAX_EVIDENCE_WINDOW: i64 = 7 * 24 * 60 * 60; // 7 days in seconds
+
+/// Append-only evidence trail for a dispute
+#[account]
+#[derive(InitSpace)]
+pub struct DisputeEvidence {
+    pub dispute: Pubkey,
+    pub deadline: i64,
+    #[max_len(MAX_EVIDENCE_ENTRIES)]
+    pub entries: Vec<EvidenceEntry>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct EvidenceEntry {
+    pub submitter: Pubkey,
+    pub content_hash: [u8; 32],
+    #[max_len(MAX_EVIDENCE_URI_LEN)]
+    pub uri: String,
+    pub submitted_at: i64,
+}
+
+impl DisputeEvidence {
+    /// Append an entry, enforcing the evidence deadline and the submitter's entry cap
+    pub fn append(
+        &mut self,
+        submitter: Pubkey,
+        content_hash: [u8; 32],
+        uri: String,
+        now: i64,
+    ) -> Result<()> {
+        require!(now <= self.deadline, EscrowError::EvidenceWindowClosed);
+        let submitted = self.entries.iter().filter(|e| e.submitter == submitter).count();
+        require!(
+            submitted < MAX_EVIDENCE_ENTRIES_PER_SUBMITTER && self.entries.len() < MAX_EVIDENCE_ENTRIES,
+            EscrowError::EvidenceLimitReached
+        );
+        require!(uri.len() <= MAX_EVIDENCE_URI_LEN, EscrowError::EvidenceUriTooLong);
+        
+        self.entries.push(EvidenceEntry {
+            submitter,
+            content_hash,
+            uri,
+            submitted_at: now,
+        });
+        
+        Ok(())
+    }
+}
+
+/// Buyer or seller adds an entry to the dispute's evidence trail
+pub fn submit_dispute_evidence(
+    ctx: Context<SubmitDisputeEvidence>,
+    content_hash: [u8; 32],
+    uri: String,
+) -> Result<()> {
+    let evidence = &mut ctx.accounts.evidence;
+    let submitter = ctx.accounts.submitter.key();
+    require!(ctx.accounts.dispute.outcome.is_none(), EscrowError::DisputeAlreadyFinalized);
+    
+    let clock = Clock::get()?;
+    evidence.append(submitter, content_hash, uri.clone(), clock.unix_timestamp)?;
+    
+    emit!(DisputeEvidenceSubmitted {
+        escrow_id: ctx.accounts.escrow.escrow_id,
+        submitter,
+        index: (evidence.entries.len() - 1) as u8,
+        content_hash,
+        uri,
+    });
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SubmitDisputeEvidence<'info> {
+    pub submitter: Signer<'info>,
+    #[account(
+        seeds = [b"escrow", escrow.escrow_id.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.buyer == submitter.key() || escrow.seller == submitter.key()
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+    #[account(
+        seeds = [b"dispute", escrow.key().as_ref(), &[escrow.current_milestone]],
+        bump = dispute.bump
+    )]
+    pub dispute: Account<'info, Dispute>,
+    #[account(
+        mut,
+        seeds = [b"evidence", dispute.key().as_ref()],
+        bump = evidence.bump
+    )]
+    pub evidence: Account<'info, DisputeEvidence>,
+}
+
+#[event]
+pub struct DisputeEvidenceSubmitted {
+    pub escrow_id: [u8; 32],
+    pub submitter: Pubkey,
+    pub index: u8,
+    pub content_hash: [u8; 32],
+    pub uri: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEADLINE: i64 = 1_000;
+
+    fn evidence() -> DisputeEvidence {
+        DisputeEvidence {
+            dispute: Pubkey::new_unique(),
+            deadline: DEADLINE,
+            entries: Vec::new(),
+            bump: 0,
+        }
+    }
+
+    fn append(evidence: &mut DisputeEvidence, submitter: Pubkey) -> Result<()> {
+        evidence.append(submitter, [1; 32], "ipfs://evidence".to_string(), DEADLINE)
+    }
+
+    #[test]
+    fn append_records_entry() {
+        let mut evidence = evidence();
+        let submitter = Pubkey::new_unique();
+        append(&mut evidence, submitter).unwrap();
+        
+        assert_eq!(evidence.entries.len(), 1);
+        assert_eq!(evidence.entries[0].submitter, submitter);
+        assert_eq!(evidence.entries[0].submitted_at, DEADLINE);
+    }
+
+    #[test]
+    fn append_rejects_after_deadline() {
+        let mut evidence = evidence();
+        let result = evidence.append(Pubkey::new_unique(), [1; 32], String::new(), DEADLINE + 1);
+        assert_eq!(result.unwrap_err(), EscrowError::EvidenceWindowClosed.into());
+        assert!(evidence.entries.is_empty());
+    }
+
+    #[test]
+    fn append_caps_entries_per_submitter() {
+        let mut evidence = evidence();
+        let buyer = Pubkey::new_unique();
+        for _ in 0..MAX_EVIDENCE_ENTRIES_PER_SUBMITTER {
+            append(&mut evidence, buyer).unwrap();
+        }
+        
+        assert_eq!(append(&mut evidence, buyer).unwrap_err(), EscrowError::EvidenceLimitReached.into());
+        // The cap is per submitter, so the counterparty can still respond
+        append(&mut evidence, Pubkey::new_unique()).unwrap();
+    }
+
+    #[test]
+    fn append_caps_total_entries_including_agent_reports() {
+        let mut evidence = evidence();
+        let buyer = Pubkey::new_unique();
+        let seller = Pubkey::new_unique();
+        for _ in 0..MAX_VERIFIER_AGENTS {
+            append(&mut evidence, Pubkey::new_unique()).unwrap();
+        }
+        for _ in 0..MAX_EVIDENCE_ENTRIES_PER_SUBMITTER {
+            append(&mut evidence, seller).unwrap();
+        }
+        for _ in 1..MAX_EVIDENCE_ENTRIES_PER_SUBMITTER {
+            append(&mut evidence, buyer).unwrap();
+        }
+        
+        // One short of the buyer's own cap, but the agents' reports used up the trail
+        assert_eq!(evidence.entries.len(), MAX_EVIDENCE_ENTRIES - 1);
+        append(&mut evidence, Pubkey::new_unique()).unwrap();
+        assert_eq!(append(&mut evidence, buyer).unwrap_err(), EscrowError::EvidenceLimitReached.into());
+    }
+
+    #[test]
+    fn append_limits_uri_length() {
+        let mut evidence = evidence();
+        let submitter = Pubkey::new_unique();
+        let uri = "a".repeat(MAX_EVIDENCE_URI_LEN);
+        evidence.append(submitter, [1; 32], uri.clone(), DEADLINE).unwrap();
+        
+        let result = evidence.append(submitter, [1; 32], uri + "a", DEADLINE);
+        assert_eq!(result.unwrap_err(), EscrowError::EvidenceUriTooLong.into());
+        assert_eq!(evidence.entries.len(), 1);
+    }
+}
@@ -1,25 +1,97 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer, Mint};
+use anchor_lang::system_program;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use solana_sdk_ids::sysvar::instructions as sysvar_instructions;
 
+mod agent;
+mod arbitration;
+mod ed25519;
+mod evidence;
+mod supported_mint;
 mod universal;
+mod vault;
+mod verification;
+use agent::*;
+use arbitration::*;
+use evidence::*;
+use supported_mint::*;
 use universal::*;
+use vault::*;
+use verification::*;
 
 declare_id!("AETHRLockEscrow11111111111111111111111111111");
 
+/// How long an oracle request may stay pending before it can be re-issued or cancelled
+pub const CHAINLINK_REQUEST_TIMEOUT: i64 = 60 * 60; // 1 hour in seconds
+
+/// Basis point denominator used for all fee calculations
+pub const BPS_DENOMINATOR: u64 = 10_000;
+/// Protocol fee applied until the authority configures another rate (10%)
+pub const DEFAULT_FEE_BPS: u16 = 1_000;
+/// Upper bound the authority may set the protocol fee to (50%)
+pub const MAX_FEE_BPS: u16 = 5_000;
+
+/// Time after a passing verdict during which only the buyer may release
+pub const DEFAULT_CHALLENGE_PERIOD: i64 = 24 * 60 * 60; // 24 hours in seconds
+/// Upper bound the authority may set the challenge period to
+pub const MAX_CHALLENGE_PERIOD: i64 = 7 * 24 * 60 * 60; // 7 days in seconds
+
+/// Maximum number of milestones (tranches) in a single escrow
+pub const MAX_MILESTONES: usize = 10;
+/// Maximum number of AI agents verifying a single escrow
+pub const MAX_VERIFIER_AGENTS: usize = 5;
+
+/// Upper bound on how long the buyer may take to review a delivery
+pub const MAX_REVIEW_WINDOW: i64 = 30 * 24 * 60 * 60; // 30 days in seconds
+
+/// `token_mint` recorded on escrows that hold native SOL instead of an SPL token
+pub const NATIVE_SOL_MINT: Pubkey = Pubkey::new_from_array([0; 32]);
+
 #[program]
 pub mod aetherlock_escrow {
     use super::*;
 
-    /// Initialize protocol configuration with authorized admin addresses
+    /// Initialize protocol configuration with authorized admin addresses.
+    /// `domain_separator` identifies the cluster in signed verification payloads.
     pub fn initialize_config(
         ctx: Context<InitializeConfig>,
         admin_pubkeys: Vec<Pubkey>,
+        domain_separator: [u8; 32],
     ) -> Result<()> {
         let config = &mut ctx.accounts.config;
         require!(admin_pubkeys.len() <= 5, EscrowError::TooManyAdmins);
         
         config.authority = ctx.accounts.authority.key();
         config.admin_pubkeys = admin_pubkeys;
+        config.fee_bps = DEFAULT_FEE_BPS;
+        config.min_fee = 0;
+        config.max_fee = 0;
+        config.treasury_owner = ctx.accounts.authority.key();
+        config.challenge_period = DEFAULT_CHALLENGE_PERIOD;
+        config.arbitrators = Vec::new();
+        config.panel_size = DEFAULT_PANEL_SIZE;
+        config.quorum = DEFAULT_QUORUM;
+        config.vote_threshold_bps = DEFAULT_VOTE_THRESHOLD_BPS;
+        config.arbitration_cursor = 0;
+        config.default_resolution = DefaultResolutionPolicy::FollowAiVerdict;
+        config.dispute_bond = DisputeBond::None;
+        config.bond_forfeit = BondForfeitDestination::Counterparty;
+        config.agent_stake_mint = NATIVE_SOL_MINT;
+        config.min_agent_stake = 0;
+        config.agent_slash_bps = DEFAULT_AGENT_SLASH_BPS;
+        config.agent_unbonding_period = MIN_AGENT_UNBONDING_PERIOD;
+        config.require_registered_agent = false;
+        config.dispute_window = WindowConfig {
+            default: DEFAULT_DISPUTE_WINDOW,
+            min: MIN_DISPUTE_WINDOW,
+            max: MAX_DISPUTE_WINDOW,
+        };
+        config.evidence_window = WindowConfig {
+            default: DEFAULT_EVIDENCE_WINDOW,
+            min: MIN_EVIDENCE_WINDOW,
+            max: MAX_EVIDENCE_WINDOW,
+        };
+        config.domain_separator = domain_separator;
         config.bump = ctx.bumps.config;
         
         Ok(())
@@ -48,161 +120,964 @@ pub mod aetherlock_escrow {
         Ok(())
     }
 
-    /// Initialize a new escrow with buyer, seller, amount, and expiry parameters
-    pub fn initialize_escrow(
-        ctx: Context<InitializeEscrow>,
+    /// Add an arbitrator to the dispute panel pool. The authority or any admin may curate the pool.
+    pub fn add_arbitrator(
+        ctx: Context<ManageArbitrators>,
+        new_arbitrator: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(config.arbitrators.len() < MAX_ARBITRATORS, EscrowError::TooManyArbitrators);
+        require!(
+            !config.arbitrators.contains(&new_arbitrator),
+            EscrowError::ArbitratorAlreadyExists
+        );
+        
+        config.arbitrators.push(new_arbitrator);
+        Ok(())
+    }
+
+    /// Remove an arbitrator from the dispute panel pool. Panels already
+    /// seated keep their members.
+    pub fn remove_arbitrator(
+        ctx: Context<ManageArbitrators>,
+        arbitrator_to_remove: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.arbitrators.retain(|&x| x != arbitrator_to_remove);
+        Ok(())
+    }
+
+    /// Update panel size, quorum and the share of votes needed to decide a dispute
+    pub fn update_arbitration_config(
+        ctx: Context<UpdateConfig>,
+        panel_size: u8,
+        quorum: u8,
+        vote_threshold_bps: u16,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(
+            quorum >= 1 && quorum <= panel_size && panel_size as usize <= MAX_PANEL_SIZE,
+            EscrowError::InvalidArbitrationConfig
+        );
+        // Anything at or below half the votes could let two outcomes both "win"
+        require!(
+            vote_threshold_bps as u64 > BPS_DENOMINATOR / 2
+                && vote_threshold_bps as u64 <= BPS_DENOMINATOR,
+            EscrowError::InvalidArbitrationConfig
+        );
+        
+        config.panel_size = panel_size;
+        config.quorum = quorum;
+        config.vote_threshold_bps = vote_threshold_bps;
+        
+        emit!(ArbitrationConfigUpdated {
+            panel_size,
+            quorum,
+            vote_threshold_bps,
+        });
+        
+        Ok(())
+    }
+
+    /// Set how disputes the panel fails to decide by their deadline are settled
+    pub fn set_default_resolution(
+        ctx: Context<UpdateConfig>,
+        policy: DefaultResolutionPolicy,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(policy.is_valid(), EscrowError::InvalidSplit);
+        
+        config.default_resolution = policy.clone();
+        
+        emit!(DefaultResolutionUpdated { policy });
+        
+        Ok(())
+    }
+
+    /// Update the protocol fee rate and its per-escrow floor and cap.
+    /// Existing escrows keep the fee they were created with.
+    pub fn update_fee_config(
+        ctx: Context<UpdateConfig>,
+        fee_bps: u16,
+        min_fee: u64,
+        max_fee: u64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(fee_bps <= MAX_FEE_BPS, EscrowError::InvalidFeeConfig);
+        require!(max_fee == 0 || min_fee <= max_fee, EscrowError::InvalidFeeConfig);
+        
+        let old_fee_bps = config.fee_bps;
+        config.fee_bps = fee_bps;
+        config.min_fee = min_fee;
+        config.max_fee = max_fee;
+        
+        emit!(FeeUpdated {
+            old_fee_bps,
+            fee_bps,
+            min_fee,
+            max_fee,
+        });
+        
+        Ok(())
+    }
+
+    /// Rotate the owner of the protocol treasury. Fees are paid to the
+    /// owner's associated token account for each escrow mint.
+    pub fn set_treasury(
+        ctx: Context<UpdateConfig>,
+        new_treasury_owner: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        
+        let old_treasury_owner = config.treasury_owner;
+        config.treasury_owner = new_treasury_owner;
+        
+        emit!(TreasuryUpdated {
+            old_treasury_owner,
+            new_treasury_owner,
+        });
+        
+        Ok(())
+    }
+
+    /// Update the challenge period that must pass after a verdict before anyone
+    /// other than the buyer can release. Existing escrows keep their period.
+    pub fn update_challenge_period(
+        ctx: Context<UpdateConfig>,
+        challenge_period: i64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(
+            (0..=MAX_CHALLENGE_PERIOD).contains(&challenge_period),
+            EscrowError::InvalidChallengePeriod
+        );
+        
+        let old_challenge_period = config.challenge_period;
+        config.challenge_period = challenge_period;
+        
+        emit!(ChallengePeriodUpdated {
+            old_challenge_period,
+            challenge_period,
+        });
+        
+        Ok(())
+    }
+
+    /// Update the default dispute and evidence windows and the bounds escrows may pick from
+    pub fn update_dispute_windows(
+        ctx: Context<UpdateConfig>,
+        dispute_window: WindowConfig,
+        evidence_window: WindowConfig,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(
+            dispute_window.is_valid()
+                && evidence_window.is_valid()
+                && evidence_window.default <= dispute_window.default,
+            EscrowError::InvalidDisputeWindow
+        );
+        
+        config.dispute_window = dispute_window;
+        config.evidence_window = evidence_window;
+        
+        emit!(DisputeWindowsUpdated {
+            dispute_window,
+            evidence_window,
+        });
+        
+        Ok(())
+    }
+
+    /// Set the bond raising a dispute costs and where it goes if the raiser loses.
+    /// Open disputes keep the terms they were raised under.
+    pub fn set_dispute_bond(
+        ctx: Context<UpdateConfig>,
+        bond: DisputeBond,
+        forfeit: BondForfeitDestination,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(bond.is_valid(), EscrowError::InvalidDisputeBond);
+        
+        config.dispute_bond = bond;
+        config.bond_forfeit = forfeit;
+        
+        emit!(DisputeBondUpdated { bond, forfeit });
+        
+        Ok(())
+    }
+
+    /// Change the domain separator AI agents sign verdicts under. Verdicts
+    /// signed under the old one stop verifying.
+    pub fn set_domain_separator(
+        ctx: Context<UpdateConfig>,
+        domain_separator: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.config.domain_separator = domain_separator;
+        
+        emit!(DomainSeparatorUpdated { domain_separator });
+        
+        Ok(())
+    }
+
+    /// Configure agent staking: the stake mint and minimum for new registrations,
+    /// the share slashed per overturned verdict, how long stake stays bonded,
+    /// and whether escrows must use registered agents
+    pub fn update_agent_config(
+        ctx: Context<UpdateConfig>,
+        stake_mint: Pubkey,
+        min_stake: u64,
+        slash_bps: u16,
+        unbonding_period: i64,
+        require_registered_agent: bool,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(slash_bps as u64 <= BPS_DENOMINATOR, EscrowError::InvalidAgentConfig);
+        require!(
+            unbonding_period >= MIN_AGENT_UNBONDING_PERIOD,
+            EscrowError::InvalidAgentConfig
+        );
+        // Slashing only deters agents that have something at stake
+        require!(
+            !require_registered_agent || min_stake > 0,
+            EscrowError::InvalidAgentConfig
+        );
+        
+        config.agent_stake_mint = stake_mint;
+        config.min_agent_stake = min_stake;
+        config.agent_slash_bps = slash_bps;
+        config.agent_unbonding_period = unbonding_period;
+        config.require_registered_agent = require_registered_agent;
+        
+        emit!(AgentConfigUpdated {
+            stake_mint,
+            min_stake,
+            slash_bps,
+            unbonding_period,
+            require_registered_agent,
+        });
+        
+        Ok(())
+    }
+
+    /// Allowlist a mint for new escrows, with amount limits and an optional fee override
+    pub fn add_supported_mint(
+        ctx: Context<AddSupportedMint>,
+        mint: Pubkey,
+        min_amount: u64,
+        max_amount: u64,
+        fee_bps: Option<u16>,
+    ) -> Result<()> {
+        supported_mint::add_supported_mint(ctx, mint, min_amount, max_amount, fee_bps)
+    }
+
+    /// Update or disable an allowlisted mint
+    pub fn update_supported_mint(
+        ctx: Context<UpdateSupportedMint>,
+        enabled: bool,
+        min_amount: u64,
+        max_amount: u64,
+        fee_bps: Option<u16>,
+    ) -> Result<()> {
+        supported_mint::update_supported_mint(ctx, enabled, min_amount, max_amount, fee_bps)
+    }
+
+    /// Register an AI agent with a stake in the protocol's stake mint
+    pub fn register_agent<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RegisterAgent<'info>>,
+        model_id: [u8; 32],
+        endpoint_hash: [u8; 32],
+        stake_amount: u64,
+    ) -> Result<()> {
+        agent::register_agent(ctx, model_id, endpoint_hash, stake_amount)
+    }
+
+    /// Add to a registered agent's stake
+    pub fn add_agent_stake<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AddAgentStake<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        agent::add_agent_stake(ctx, amount)
+    }
+
+    /// Update a registered agent's model identifier and endpoint hash
+    pub fn update_agent_metadata(
+        ctx: Context<UpdateAgent>,
+        model_id: [u8; 32],
+        endpoint_hash: [u8; 32],
+    ) -> Result<()> {
+        agent::update_agent_metadata(ctx, model_id, endpoint_hash)
+    }
+
+    /// Add a signing key to a registered agent, with a validity window
+    pub fn add_agent_signing_key(
+        ctx: Context<UpdateAgent>,
+        key: Pubkey,
+        valid_from: i64,
+        valid_until: Option<i64>,
+    ) -> Result<()> {
+        agent::add_agent_signing_key(ctx, key, valid_from, valid_until)
+    }
+
+    /// Revoke one of a registered agent's signing keys
+    pub fn revoke_agent_signing_key(ctx: Context<UpdateAgent>, key: Pubkey) -> Result<()> {
+        agent::revoke_agent_signing_key(ctx, key)
+    }
+
+    /// Suspend or reinstate a registered agent
+    pub fn set_agent_status(ctx: Context<SetAgentStatus>, status: AgentStatus) -> Result<()> {
+        agent::set_agent_status(ctx, status)
+    }
+
+    /// Retire an agent and start unbonding its stake
+    pub fn begin_agent_unbonding(ctx: Context<UpdateAgent>) -> Result<()> {
+        agent::begin_agent_unbonding(ctx)
+    }
+
+    /// Withdraw an unbonded agent's stake to its operator
+    pub fn withdraw_agent_stake<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawAgentStake<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        agent::withdraw_agent_stake(ctx, amount)
+    }
+
+    /// Slash the stake of an agent whose verdict a dispute panel overturned
+    pub fn slash_agent<'info>(ctx: Context<'_, '_, 'info, 'info, SlashAgent<'info>>) -> Result<()> {
+        agent::slash_agent(ctx)
+    }
+
+    /// Initialize a new escrow with buyer, seller, amount, and delivery deadline parameters.
+    /// Omitting the token mint creates a native SOL escrow.
+    /// `milestones` splits the amount into tranches that are verified and released
+    /// in order; an empty list creates a single tranche due at the delivery deadline.
+    /// Each delivery gives the buyer `review_window` seconds before it auto-releases.
+    /// Dispute and evidence windows default to the protocol's when not given.
+    /// The seller must accept the terms by `acceptance_deadline` before funding.
+    /// `co_agents` verify alongside `ai_agent_pubkey`; a milestone is decided once
+    /// `verdict_threshold` of their verdicts agree. Passing verdicts below
+    /// `min_confidence_bps` go to dispute instead.
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_escrow<'info>(
+        ctx: Context<'_, '_, 'info, 'info, InitializeEscrow<'info>>,
         escrow_id: [u8; 32],
         seller: Pubkey,
         amount: u64,
-        expiry: i64,
+        delivery_deadline: i64,
+        review_window: i64,
         metadata_hash: [u8; 32],
         ai_agent_pubkey: Pubkey,
+        milestones: Vec<MilestoneParams>,
+        acceptance_deadline: i64,
+        dispute_window: Option<i64>,
+        evidence_window: Option<i64>,
+        co_agents: Vec<Pubkey>,
+        verdict_threshold: u8,
+        min_confidence_bps: u16,
     ) -> Result<()> {
         let escrow = &mut ctx.accounts.escrow;
+        let config = &ctx.accounts.config;
+        let supported_mint = &ctx.accounts.supported_mint;
+        let token_mint = match ctx.accounts.token_mint.as_ref() {
+            Some(token_mint) => {
+                validate_mint_extensions(token_mint)?;
+                token_mint.key()
+            },
+            None => NATIVE_SOL_MINT,
+        };
+        require_keys_eq!(supported_mint.mint, token_mint, EscrowError::UnsupportedMint);
+        supported_mint.check_amount(amount)?;
+        let fee_bps = supported_mint.escrow_fee_bps(config);
+        let dispute_window = config.dispute_window.resolve(dispute_window)?;
+        let evidence_window = config.evidence_window.resolve(evidence_window)?;
+        require!(evidence_window <= dispute_window, EscrowError::InvalidDisputeWindow);
         
-        // Calculate 10% protocol fee
-        let fee_amount = amount
-            .checked_mul(10)
-            .ok_or(EscrowError::MathOverflow)?
-            .checked_div(100)
-            .ok_or(EscrowError::MathOverflow)?;
+        // Registered agents stake on their verdicts; the protocol may insist on one
+        match ctx.accounts.ai_agent.as_ref() {
+            Some(ai_agent) => {
+                require_keys_eq!(ai_agent.agent, ai_agent_pubkey, EscrowError::UnregisteredAgent);
+                require!(ai_agent.status == AgentStatus::Active, EscrowError::AgentSuspended);
+            },
+            None => require!(!config.require_registered_agent, EscrowError::UnregisteredAgent),
+        }
+        // Registry entries for co-agents follow as remaining accounts
+        if config.require_registered_agent {
+            require_registered_agents(&co_agents, ctx.remaining_accounts)?;
+        }
+        
+        let mut ai_agents = vec![ai_agent_pubkey];
+        for agent in co_agents {
+            require!(!ai_agents.contains(&agent), EscrowError::InvalidAgentQuorum);
+            ai_agents.push(agent);
+        }
+        // A strict majority, so the two verdicts can never both reach the threshold
+        require!(
+            ai_agents.len() <= MAX_VERIFIER_AGENTS
+                && verdict_threshold as usize * 2 > ai_agents.len()
+                && verdict_threshold as usize <= ai_agents.len(),
+            EscrowError::InvalidAgentQuorum
+        );
+        require!(
+            min_confidence_bps as u64 <= BPS_DENOMINATOR,
+            EscrowError::InvalidConfidence
+        );
+        
+        let milestones = if milestones.is_empty() {
+            vec![MilestoneParams { amount, deadline: delivery_deadline }]
+        } else {
+            milestones
+        };
+        require!(milestones.len() <= MAX_MILESTONES, EscrowError::InvalidMilestones);
+        require!(
+            acceptance_deadline <= delivery_deadline,
+            EscrowError::InvalidAcceptanceDeadline
+        );
+        require!(
+            review_window > 0 && review_window <= MAX_REVIEW_WINDOW,
+            EscrowError::InvalidReviewWindow
+        );
+        
+        // Snapshot the current protocol fee so later config changes don't affect this escrow.
+        // The floor and cap apply to the escrow as a whole; the fee is then split across the
+        // tranches in proportion to their amounts and charged as each milestone is released.
+        let fee_amount = config.calculate_fee(amount, fee_bps)?;
+        let mut total_amount: u64 = 0;
+        let mut charged_fee: u64 = 0;
+        let mut previous_deadline = i64::MIN;
+        let mut schedule = Vec::with_capacity(milestones.len());
+        for params in milestones {
+            require!(
+                params.amount > 0
+                    && params.deadline >= previous_deadline
+                    && params.deadline <= delivery_deadline,
+                EscrowError::InvalidMilestones
+            );
+            
+            total_amount = total_amount
+                .checked_add(params.amount)
+                .ok_or(EscrowError::MathOverflow)?;
+            // Pro rata on the running total, so rounding never pushes a tranche's
+            // fee above its amount and the tranche fees sum to the escrow fee
+            let cumulative_fee = (fee_amount as u128)
+                .checked_mul(total_amount as u128)
+                .ok_or(EscrowError::MathOverflow)?
+                .checked_div(amount as u128)
+                .ok_or(EscrowError::MathOverflow)?
+                .min(fee_amount as u128) as u64;
+            let milestone_fee = cumulative_fee
+                .checked_sub(charged_fee)
+                .ok_or(EscrowError::MathOverflow)?;
+            charged_fee = cumulative_fee;
+            previous_deadline = params.deadline;
+            
+            schedule.push(Milestone {
+                amount: params.amount,
+                fee_amount: milestone_fee,
+                deadline: params.deadline,
+                delivered_at: None,
+                evidence_hash: None,
+                verification_result: None,
+                status: EscrowStatus::Created,
+            });
+        }
+        require!(total_amount == amount, EscrowError::InvalidMilestones);
         
         escrow.escrow_id = escrow_id;
         escrow.buyer = ctx.accounts.buyer.key();
         escrow.seller = seller;
-        escrow.token_mint = ctx.accounts.token_mint.key();
+        escrow.token_mint = token_mint;
         escrow.amount = amount;
         escrow.fee_amount = fee_amount;
+        escrow.fee_bps = fee_bps;
+        escrow.challenge_period = config.challenge_period;
+        escrow.dispute_window = dispute_window;
+        escrow.evidence_window = evidence_window;
         escrow.status = EscrowStatus::Created;
-        escrow.expiry = expiry;
+        escrow.delivery_deadline = delivery_deadline;
+        escrow.review_window = review_window;
+        escrow.delivered_at = None;
+        escrow.auto_release_after = None;
+        escrow.acceptance_deadline = acceptance_deadline;
+        escrow.accepted_at = None;
         escrow.metadata_hash = metadata_hash;
         escrow.verification_result = None;
         escrow.evidence_hash = None;
         escrow.dispute_raised = false;
         escrow.dispute_deadline = None;
+        escrow.open_disputes = 0;
         escrow.ai_agent_pubkey = ai_agent_pubkey;
+        escrow.ai_agents = ai_agents;
+        escrow.verdict_threshold = verdict_threshold;
+        escrow.agent_verdicts = Vec::new();
+        escrow.min_confidence_bps = min_confidence_bps;
+        escrow.confidence_bps = None;
+        escrow.model_hash = None;
+        escrow.report_hash = None;
+        escrow.agent_nonces = vec![0; escrow.ai_agents.len()];
+        escrow.seller_payout = None;
+        escrow.milestones = schedule;
+        escrow.current_milestone = 0;
+        escrow.funded_at = None;
+        escrow.buyer_cancel_approval = None;
+        escrow.seller_cancel_approval = None;
         escrow.bump = ctx.bumps.escrow;
         
         Ok(())
     }
 
-    /// Deposit funds from buyer to escrow PDA
-    pub fn deposit_funds(ctx: Context<DepositFunds>) -> Result<()> {
+    /// Seller accepts the escrow by signing over the terms it was created with
+    pub fn accept_escrow(ctx: Context<AcceptEscrow>, metadata_hash: [u8; 32]) -> Result<()> {
         let escrow = &mut ctx.accounts.escrow;
         require!(escrow.status == EscrowStatus::Created, EscrowError::InvalidEscrowState);
+        require!(metadata_hash == escrow.metadata_hash, EscrowError::TermsMismatch);
         
-        // Transfer tokens to escrow vault
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.buyer_token_account.to_account_info(),
-                    to: ctx.accounts.escrow_vault.to_account_info(),
-                    authority: ctx.accounts.buyer.to_account_info(),
-                },
-            ),
-            escrow.amount,
+        let clock = Clock::get()?;
+        require!(
+            clock.unix_timestamp <= escrow.acceptance_deadline,
+            EscrowError::AcceptanceExpired
+        );
+        
+        for milestone in escrow.milestones.iter_mut() {
+            milestone.status = EscrowStatus::Accepted;
+        }
+        escrow.accepted_at = Some(clock.unix_timestamp);
+        escrow.status = EscrowStatus::Accepted;
+        
+        emit!(EscrowAccepted {
+            escrow_id: escrow.escrow_id,
+            seller: escrow.seller,
+            metadata_hash,
+        });
+        
+        Ok(())
+    }
+
+    /// Seller sets (or clears) an alternate token account, or wallet for native SOL
+    /// escrows, to receive their payout
+    pub fn set_seller_payout(ctx: Context<SetSellerPayout>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        require!(!escrow.status.is_terminal(), EscrowError::InvalidEscrowState);
+        
+        let payout = if escrow.is_native() {
+            require!(ctx.accounts.payout_account.is_none(), EscrowError::InvalidPayoutAccount);
+            ctx.accounts.payout_wallet.as_ref().map(|wallet| wallet.key())
+        } else {
+            require!(ctx.accounts.payout_wallet.is_none(), EscrowError::InvalidPayoutAccount);
+            ctx.accounts.payout_account.as_ref().map(|account| account.key())
+        };
+        escrow.seller_payout = payout;
+        
+        emit!(SellerPayoutUpdated {
+            escrow_id: escrow.escrow_id,
+            payout,
+        });
+        
+        Ok(())
+    }
+
+    /// Deposit funds from buyer to escrow PDA. Native SOL escrows hold the
+    /// lamports on the escrow account itself; token escrows use the vault and
+    /// record what it actually received, net of any Token-2022 transfer fee.
+    pub fn deposit_funds<'info>(ctx: Context<'_, '_, 'info, 'info, DepositFunds<'info>>) -> Result<()> {
+        let escrow_account_info = ctx.accounts.escrow.to_account_info();
+        let vault = VaultAccounts::resolve(
+            ctx.accounts.escrow.is_native(),
+            ctx.accounts.token_program.as_ref(),
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.token_mint.as_ref(),
+            ctx.remaining_accounts,
         )?;
+        let escrow = &mut ctx.accounts.escrow;
+        require!(escrow.status == EscrowStatus::Accepted, EscrowError::InvalidEscrowState);
         
+        if let Some(vault) = vault.as_ref() {
+            let buyer_token_account = ctx.accounts
+                .buyer_token_account
+                .as_ref()
+                .ok_or(EscrowError::MissingSettlementAccount)?;
+            
+            // Transfer tokens to escrow vault
+            let vault_info = vault.escrow_vault.to_account_info();
+            let balance_before = vault_balance(&vault_info)?;
+            vault.deposit(
+                buyer_token_account.to_account_info(),
+                ctx.accounts.buyer.to_account_info(),
+                escrow.amount,
+            )?;
+            let received = vault_balance(&vault_info)?
+                .checked_sub(balance_before)
+                .ok_or(EscrowError::MathOverflow)?;
+            
+            escrow.record_deposit(received)?;
+        } else {
+            // Transfer lamports to escrow PDA
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.buyer.to_account_info(),
+                        to: escrow_account_info,
+                    },
+                ),
+                escrow.amount,
+            )?;
+        }
+        
+        for milestone in escrow.milestones.iter_mut() {
+            milestone.status = EscrowStatus::Funded;
+        }
+        escrow.funded_at = Some(Clock::get()?.unix_timestamp);
         escrow.status = EscrowStatus::Funded;
         Ok(())
     }
 
-    /// Submit Chainlink oracle request for AI verification
+    /// Submit Chainlink oracle request for AI verification of the current milestone,
+    /// or re-issue a timed out one
     pub fn request_chainlink_verification(
-        ctx: Context<SubmitVerification>,
+        ctx: Context<RequestVerification>,
+        milestone_index: u8,
         evidence_hash: [u8; 32],
         chainlink_request_id: [u8; 32],
     ) -> Result<()> {
         let escrow = &mut ctx.accounts.escrow;
-        require!(escrow.status == EscrowStatus::Funded, EscrowError::InvalidEscrowState);
+        escrow.require_current_milestone(milestone_index)?;
+        require!(!escrow.dispute_raised, EscrowError::DisputeActive);
+        
+        let clock = Clock::get()?;
+        let previous_request_id = match escrow.status {
+            EscrowStatus::Funded => None,
+            EscrowStatus::PendingVerification => {
+                require!(
+                    escrow.chainlink_request_timed_out(clock.unix_timestamp),
+                    EscrowError::ChainlinkRequestPending
+                );
+                escrow.chainlink_request_id
+            },
+            _ => return Err(EscrowError::InvalidEscrowState.into()),
+        };
         
         escrow.evidence_hash = Some(evidence_hash);
         escrow.chainlink_request_id = Some(chainlink_request_id);
-        escrow.status = EscrowStatus::PendingVerification;
+        escrow.chainlink_requested_at = Some(clock.unix_timestamp);
+        escrow.agent_verdicts.clear();
+        escrow.current_milestone_mut()?.evidence_hash = Some(evidence_hash);
+        escrow.set_status(EscrowStatus::PendingVerification);
         
         emit!(ChainlinkVerificationRequested {
             escrow_id: escrow.escrow_id,
+            milestone_index,
             evidence_hash,
             request_id: chainlink_request_id,
+            previous_request_id,
+            expires_at: clock.unix_timestamp + CHAINLINK_REQUEST_TIMEOUT,
         });
         
         Ok(())
     }
 
-    /// Submit AI verification result with Chainlink oracle signature
-    pub fn submit_verification(
-        ctx: Context<SubmitVerification>,
-        result: bool,
-        evidence_hash: [u8; 32],
-        timestamp: i64,
+    /// Cancel a timed out Chainlink request and return the escrow to Funded
+    pub fn cancel_chainlink_verification(ctx: Context<RequestVerification>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        require!(
+            escrow.status == EscrowStatus::PendingVerification,
+            EscrowError::InvalidEscrowState
+        );
+        
+        let clock = Clock::get()?;
+        require!(
+            escrow.chainlink_request_timed_out(clock.unix_timestamp),
+            EscrowError::ChainlinkRequestPending
+        );
+        
+        let request_id = escrow
+            .chainlink_request_id
+            .ok_or(EscrowError::InvalidChainlinkRequest)?;
+        
+        escrow.chainlink_request_id = None;
+        escrow.chainlink_requested_at = None;
+        escrow.set_status(EscrowStatus::Funded);
+        
+        emit!(ChainlinkVerificationCancelled {
+            escrow_id: escrow.escrow_id,
+            milestone_index: escrow.current_milestone,
+            request_id,
+        });
+        
+        Ok(())
+    }
+
+    /// Submit one agent's AI verification verdict for the current milestone with
+    /// Chainlink oracle signature, signed by one of the agent's currently valid
+    /// signing keys, or its own key if it never registered. The milestone is
+    /// verified once the escrow's verdict threshold agrees; if the verdicts split
+    /// so neither side can reach it, or agree on a pass below the escrow's
+    /// minimum confidence, a dispute is opened instead, and the other registered
+    /// agents' registry entries are passed as remaining accounts in verdict order.
+    pub fn submit_verification<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SubmitVerification<'info>>,
+        milestone_index: u8,
+        payload: VerificationPayload,
         signature: [u8; 64],
         chainlink_request_id: [u8; 32],
     ) -> Result<()> {
         let escrow = &mut ctx.accounts.escrow;
-        require!(escrow.status == EscrowStatus::Funded, EscrowError::InvalidEscrowState);
+        payload.validate()?;
+        escrow.require_current_milestone(milestone_index)?;
+        require!(
+            escrow.status == EscrowStatus::PendingVerification,
+            EscrowError::InvalidEscrowState
+        );
         require!(!escrow.dispute_raised, EscrowError::DisputeActive);
         
-        // Verify AI agent public key matches stored key
-        let ai_agent_key = ctx.accounts.ai_agent.key();
+        // A registered agent signs with a key its registry entry currently vouches
+        // for; an agent that never registered signs with its own key
+        let clock = Clock::get()?;
+        let signing_key = ctx.accounts.signing_key.key();
+        let registered = ctx.accounts.ai_agent.is_some();
+        let ai_agent_key = match (
+            ctx.accounts.ai_agent.as_ref(),
+            ctx.accounts.unregistered_agent.as_ref(),
+        ) {
+            (Some(ai_agent), None) => {
+                require!(ai_agent.status == AgentStatus::Active, EscrowError::AgentSuspended);
+                require!(
+                    ai_agent.is_signing_key(&signing_key, clock.unix_timestamp),
+                    EscrowError::UnknownSigningKey
+                );
+                ai_agent.agent
+            },
+            (None, Some(_)) => signing_key,
+            _ => return err!(EscrowError::UnregisteredAgent),
+        };
+        
+        // Verify AI agent is one of the escrow's agents and has not voted yet
         require!(
-            ai_agent_key == escrow.ai_agent_pubkey,
+            escrow.ai_agents.contains(&ai_agent_key),
             EscrowError::UnauthorizedAIAgent
         );
+        require!(
+            !escrow.agent_verdicts.iter().any(|verdict| verdict.agent == ai_agent_key),
+            EscrowError::AgentAlreadyVoted
+        );
         
         // Construct verification payload message
-        // Format: escrow_id (32 bytes) + result (1 byte) + evidence_hash (32 bytes) + timestamp (8 bytes)
-        let mut message = Vec::with_capacity(73);
-        message.extend_from_slice(&escrow.escrow_id);
-        message.push(if result { 1u8 } else { 0u8 });
-        message.extend_from_slice(&evidence_hash);
-        message.extend_from_slice(&timestamp.to_le_bytes());
+        let message = payload.message(
+            &ctx.accounts.config.domain_separator,
+            &escrow.escrow_id,
+            milestone_index,
+            &chainlink_request_id,
+        );
         
-        // Verify Ed25519 signature (simplified for now)
-        let ai_agent_pubkey_bytes = ai_agent_key.to_bytes();
+        // Verify Ed25519 signature via the native program instruction preceding this one
+        ed25519::verify_ed25519_instruction(
+            &ctx.accounts.instructions_sysvar,
+            &signing_key,
+            &message,
+            &signature,
+        )?;
         
-        // Verify the signature - simplified signature check
-        // In production, use proper ed25519 verification library
-        require!(signature.len() == 64, EscrowError::InvalidSignature);
+        // Each signed verdict is accepted once; every agent's nonces only move
+        // forward, independently of the other agents
+        let agent_index = escrow
+            .ai_agents
+            .iter()
+            .position(|agent| *agent == ai_agent_key)
+            .ok_or(EscrowError::UnauthorizedAIAgent)?;
+        let last_nonce = escrow
+            .agent_nonces
+            .get_mut(agent_index)
+            .ok_or(EscrowError::UnauthorizedAIAgent)?;
+        require!(payload.nonce > *last_nonce, EscrowError::VerificationReplayed);
+        *last_nonce = payload.nonce;
         
         // Validate timestamp is recent (within 5 minutes)
-        let clock = Clock::get()?;
-        let time_diff = (clock.unix_timestamp - timestamp).abs();
+        let time_diff = (clock.unix_timestamp - payload.timestamp).abs();
         require!(time_diff <= 300, EscrowError::TimestampTooOld);
         
-        // Verify Chainlink request ID matches
+        // Verify Chainlink request ID matches and has not timed out
         require!(
             escrow.chainlink_request_id == Some(chainlink_request_id),
             EscrowError::InvalidChainlinkRequest
         );
+        require!(
+            !escrow.chainlink_request_timed_out(clock.unix_timestamp),
+            EscrowError::ChainlinkRequestExpired
+        );
         
-        // Store the verification result
-        escrow.verification_result = Some(result);
-        escrow.evidence_hash = Some(evidence_hash);
-        escrow.status = EscrowStatus::Verified;
+        // Holds the agent's stake bonded until this verdict can no longer be overturned
+        if let Some(ai_agent) = ctx.accounts.ai_agent.as_mut() {
+            ai_agent.last_verdict_at = Some(clock.unix_timestamp);
+        }
         
-        emit!(ChainlinkVerificationCompleted {
+        let result = payload.result;
+        let evidence_hash = payload.evidence_hash;
+        escrow.agent_verdicts.push(AgentVerdict {
+            agent: ai_agent_key,
+            registered,
+            result,
+            confidence_bps: payload.confidence_bps,
+            evidence_hash,
+            model_hash: payload.model_hash,
+            report_hash: payload.report_hash,
+        });
+        let (tally, escalation) = escrow.tally_verdicts(result);
+        let VerdictTally {
+            verdicts_for,
+            verdicts_against,
+            decided,
+            confidence_bps,
+        } = tally;
+        
+        emit!(AgentVerdictSubmitted {
             escrow_id: escrow.escrow_id,
+            milestone_index,
+            agent: ai_agent_key,
             result,
-            request_id: chainlink_request_id,
+            confidence_bps: payload.confidence_bps,
+            verdicts_for,
+            verdicts_against,
         });
         
+        // Dispute accounts are initialized by this instruction, so they may only be
+        // passed when this verdict escalates the milestone
+        require!(
+            ctx.accounts.dispute.is_some() == escalation.is_some()
+                && ctx.accounts.evidence.is_some() == escalation.is_some(),
+            EscrowError::DisputeAccountsMismatch
+        );
+        
+        if let Some(reason) = escalation {
+            // Escalate to an arbitrator panel, raised by the escrow itself
+            let (Some(dispute), Some(evidence)) =
+                (ctx.accounts.dispute.as_mut(), ctx.accounts.evidence.as_mut())
+            else {
+                return err!(EscrowError::DisputeAccountsMismatch);
+            };
+            let raised_by = escrow.key();
+            open_dispute(
+                escrow,
+                &mut ctx.accounts.config,
+                dispute,
+                evidence,
+                raised_by,
+                ctx.accounts.payer.key(),
+                clock.unix_timestamp,
+            )?;
+            dispute.bump = ctx.bumps.dispute.ok_or(EscrowError::DisputeAccountsMismatch)?;
+            evidence.bump = ctx.bumps.evidence.ok_or(EscrowError::DisputeAccountsMismatch)?;
+            
+            // Every registered agent's stake stays held until the dispute is finalized
+            if let Some(ai_agent) = ctx.accounts.ai_agent.as_mut() {
+                ai_agent.add_open_dispute()?;
+            }
+            let other_agents: Vec<Pubkey> = escrow
+                .registered_verdict_agents()
+                .into_iter()
+                .filter(|agent| *agent != ai_agent_key)
+                .collect();
+            add_agent_disputes(&other_agents, ctx.remaining_accounts)?;
+            
+            // Each agent's report opens the evidence trail
+            for verdict in escrow.agent_verdicts.iter() {
+                evidence.append(verdict.agent, verdict.evidence_hash, String::new(), clock.unix_timestamp)?;
+            }
+            
+            emit!(VerificationEscalated {
+                escrow_id: escrow.escrow_id,
+                milestone_index,
+                reason,
+                verdicts_for,
+                verdicts_against,
+                confidence_bps,
+            });
+            emit!(DisputePanelSelected {
+                escrow_id: escrow.escrow_id,
+                milestone_index,
+                raised_by,
+                panel: dispute.panel.clone(),
+                deadline: dispute.deadline,
+                bond_amount: 0,
+            });
+        } else if decided {
+            // Store the verification result
+            escrow.verification_result = Some(result);
+            escrow.evidence_hash = Some(evidence_hash);
+            escrow.confidence_bps = Some(confidence_bps);
+            escrow.model_hash = Some(payload.model_hash);
+            escrow.report_hash = Some(payload.report_hash);
+            escrow.verified_at = Some(clock.unix_timestamp);
+            // A pass counts as delivery: it starts the buyer's review window so
+            // auto_settle releases the milestone instead of refunding it
+            if result && escrow.auto_release_after.is_none() {
+                escrow.auto_release_after = Some(
+                    clock.unix_timestamp
+                        .checked_add(escrow.review_window)
+                        .ok_or(EscrowError::MathOverflow)?,
+                );
+            }
+            let milestone = escrow.current_milestone_mut()?;
+            milestone.verification_result = Some(result);
+            milestone.evidence_hash = Some(evidence_hash);
+            escrow.set_status(EscrowStatus::Verified);
+            
+            emit!(ChainlinkVerificationCompleted {
+                escrow_id: escrow.escrow_id,
+                milestone_index,
+                result,
+                confidence_bps,
+                model_hash: payload.model_hash,
+                report_hash: payload.report_hash,
+                request_id: chainlink_request_id,
+            });
+        }
+        
         Ok(())
     }
 
-    /// Release funds to seller with protocol fee deduction
-    pub fn release_funds(ctx: Context<ReleaseFunds>) -> Result<()> {
+    /// Release the current milestone's tranche to seller with protocol fee deduction.
+    /// The buyer can release right away; anyone else can crank it once both the
+    /// challenge period and the buyer's review window have passed.
+    pub fn release_funds<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReleaseFunds<'info>>,
+        milestone_index: u8,
+    ) -> Result<()> {
         let escrow_account_info = ctx.accounts.escrow.to_account_info();
+        let caller = ctx.accounts.caller.key();
+        let is_native = ctx.accounts.escrow.is_native();
+        let vault = VaultAccounts::resolve(
+            is_native,
+            ctx.accounts.token_program.as_ref(),
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.token_mint.as_ref(),
+            ctx.remaining_accounts,
+        )?;
+        let seller_destination = settlement_destination(
+            is_native,
+            ctx.accounts.seller_token_account.as_ref(),
+            ctx.accounts.seller_wallet.as_ref(),
+        )?;
+        let treasury_destination = settlement_destination(
+            is_native,
+            ctx.accounts.protocol_treasury.as_ref(),
+            ctx.accounts.treasury_wallet.as_ref(),
+        )?;
         let escrow = &mut ctx.accounts.escrow;
+        escrow.require_current_milestone(milestone_index)?;
         require!(escrow.status == EscrowStatus::Verified, EscrowError::InvalidEscrowState);
         require!(escrow.verification_result == Some(true), EscrowError::VerificationFailed);
         require!(!escrow.dispute_raised, EscrowError::DisputeActive);
         
+        let clock = Clock::get()?;
+        require!(
+            caller == escrow.buyer || escrow.release_unlocked(clock.unix_timestamp),
+            EscrowError::ChallengePeriodActive
+        );
+        
         // Store immutable values to avoid borrow conflicts
-        let escrow_id = escrow.escrow_id.clone();
+        let escrow_id = escrow.escrow_id;
         let bump = escrow.bump;
-        let amount = escrow.amount;
-        let fee_amount = escrow.fee_amount;
+        let milestone = escrow.current_milestone()?;
+        let amount = milestone.amount;
+        let fee_amount = milestone.fee_amount;
         
         let seeds = &[
             b"escrow",
@@ -216,54 +1091,417 @@ pub mod aetherlock_escrow {
             .ok_or(EscrowError::MathOverflow)?;
         
         // Transfer funds to seller (amount minus fee)
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.escrow_vault.to_account_info(),
-                    to: ctx.accounts.seller_token_account.to_account_info(),
-                    authority: escrow_account_info.clone(),
-                },
-                signer,
-            ),
+        transfer_from_vault(
+            vault.as_ref(),
+            seller_destination,
+            escrow_account_info.clone(),
+            signer,
             seller_amount,
         )?;
         
-        // Transfer protocol fee to treasury
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.escrow_vault.to_account_info(),
-                    to: ctx.accounts.protocol_treasury.to_account_info(),
-                    authority: escrow_account_info.clone(),
-                },
-                signer,
-            ),
-            fee_amount,
-        )?;
+        // Transfer protocol fee to treasury
+        transfer_from_vault(
+            vault.as_ref(),
+            treasury_destination,
+            escrow_account_info,
+            signer,
+            fee_amount,
+        )?;
+        
+        escrow.settle_current_milestone(EscrowStatus::Released)?;
+        
+        emit!(MilestoneSettled {
+            escrow_id,
+            milestone_index,
+            status: EscrowStatus::Released,
+            buyer_amount: 0,
+            seller_amount,
+            fee_amount,
+        });
+        
+        Ok(())
+    }
+
+    /// Refund the current milestone's tranche to buyer if verification failed or
+    /// the milestone deadline passed
+    pub fn refund_buyer<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RefundBuyer<'info>>,
+        milestone_index: u8,
+    ) -> Result<()> {
+        let escrow_account_info = ctx.accounts.escrow.to_account_info();
+        let is_native = ctx.accounts.escrow.is_native();
+        let vault = VaultAccounts::resolve(
+            is_native,
+            ctx.accounts.token_program.as_ref(),
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.token_mint.as_ref(),
+            ctx.remaining_accounts,
+        )?;
+        let buyer_destination = match ctx.accounts.buyer_token_account.as_ref() {
+            _ if is_native => ctx.accounts.buyer.to_account_info(),
+            Some(account) => account.to_account_info(),
+            None => return err!(EscrowError::MissingSettlementAccount),
+        };
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.require_current_milestone(milestone_index)?;
+        
+        let clock = Clock::get()?;
+        let milestone = escrow.current_milestone()?;
+        let can_refund = escrow.status == EscrowStatus::Funded
+                && escrow.delivered_at.is_none()
+                && clock.unix_timestamp > milestone.deadline
+            || escrow.status == EscrowStatus::Verified && escrow.verification_result == Some(false);
+        
+        require!(can_refund, EscrowError::RefundNotAllowed);
+        
+        // Store immutable values to avoid borrow conflicts
+        let escrow_id = escrow.escrow_id;
+        let bump = escrow.bump;
+        let amount = milestone.amount;
+        
+        let seeds = &[
+            b"escrow",
+            escrow_id.as_ref(),
+            &[bump],
+        ];
+        let signer = &[&seeds[..]];
+        
+        // Refund full amount to buyer
+        transfer_from_vault(
+            vault.as_ref(),
+            buyer_destination,
+            escrow_account_info,
+            signer,
+            amount,
+        )?;
+        
+        escrow.settle_current_milestone(EscrowStatus::Refunded)?;
+        
+        emit!(MilestoneSettled {
+            escrow_id,
+            milestone_index,
+            status: EscrowStatus::Refunded,
+            buyer_amount: amount,
+            seller_amount: 0,
+            fee_amount: 0,
+        });
+        
+        Ok(())
+    }
+
+    /// Buyer cancels an escrow that has not been funded yet, e.g. to reclaim
+    /// it when the seller never accepted
+    pub fn cancel_escrow(ctx: Context<CancelEscrow>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        require!(
+            matches!(escrow.status, EscrowStatus::Created | EscrowStatus::Accepted),
+            EscrowError::InvalidEscrowState
+        );
+        
+        for milestone in escrow.milestones.iter_mut() {
+            milestone.status = EscrowStatus::Cancelled;
+        }
+        escrow.status = EscrowStatus::Cancelled;
+        
+        emit!(EscrowCancelled {
+            escrow_id: escrow.escrow_id,
+            refunded_amount: 0,
+        });
+        
+        Ok(())
+    }
+
+    /// Buyer or seller approves cancelling a funded escrow. Once both have
+    /// approved, every unsettled milestone is refunded to the buyer.
+    /// An approval only counts for the milestone and status it was given in.
+    pub fn approve_cancellation<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ApproveCancellation<'info>>,
+    ) -> Result<()> {
+        let escrow_account_info = ctx.accounts.escrow.to_account_info();
+        let is_native = ctx.accounts.escrow.is_native();
+        let approver = ctx.accounts.approver.key();
+        let escrow = &mut ctx.accounts.escrow;
+        require!(
+            matches!(
+                escrow.status,
+                EscrowStatus::Funded | EscrowStatus::PendingVerification | EscrowStatus::Verified
+            ),
+            EscrowError::InvalidEscrowState
+        );
+        require!(!escrow.dispute_raised, EscrowError::DisputeActive);
+        
+        let approval = escrow.cancellation_approval();
+        if approver == escrow.buyer {
+            escrow.buyer_cancel_approval = Some(approval.clone());
+        }
+        if approver == escrow.seller {
+            escrow.seller_cancel_approval = Some(approval.clone());
+        }
+        
+        emit!(CancellationApproved {
+            escrow_id: escrow.escrow_id,
+            approver,
+            milestone_index: approval.milestone_index,
+        });
+        
+        // Approvals given for an earlier milestone or status no longer count
+        if escrow.buyer_cancel_approval.as_ref() != Some(&approval)
+            || escrow.seller_cancel_approval.as_ref() != Some(&approval)
+        {
+            return Ok(());
+        }
+        
+        let vault = VaultAccounts::resolve(
+            is_native,
+            ctx.accounts.token_program.as_ref(),
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.token_mint.as_ref(),
+            ctx.remaining_accounts,
+        )?;
+        let buyer_destination = settlement_destination(
+            is_native,
+            ctx.accounts.buyer_token_account.as_ref(),
+            Some(&ctx.accounts.buyer),
+        )?;
+        
+        // Store immutable values to avoid borrow conflicts
+        let escrow_id = escrow.escrow_id;
+        let bump = escrow.bump;
+        let first_unsettled = escrow.current_milestone as usize;
+        
+        let mut refunded_amount: u64 = 0;
+        for milestone in escrow.milestones.iter_mut().skip(first_unsettled) {
+            refunded_amount = refunded_amount
+                .checked_add(milestone.amount)
+                .ok_or(EscrowError::MathOverflow)?;
+            milestone.status = EscrowStatus::Cancelled;
+        }
+        
+        let seeds = &[
+            b"escrow",
+            escrow_id.as_ref(),
+            &[bump],
+        ];
+        let signer = &[&seeds[..]];
+        
+        // Refund everything not yet settled to buyer
+        transfer_from_vault(
+            vault.as_ref(),
+            buyer_destination,
+            escrow_account_info,
+            signer,
+            refunded_amount,
+        )?;
+        
+        escrow.current_milestone = escrow.milestones.len() as u8;
+        escrow.status = EscrowStatus::Cancelled;
+        escrow.buyer_cancel_approval = None;
+        escrow.seller_cancel_approval = None;
+        
+        emit!(EscrowCancelled {
+            escrow_id,
+            refunded_amount,
+        });
+        
+        Ok(())
+    }
+
+    /// Buyer or seller withdraws their cancellation approval
+    pub fn revoke_cancellation(ctx: Context<RevokeCancellation>) -> Result<()> {
+        let approver = ctx.accounts.approver.key();
+        let escrow = &mut ctx.accounts.escrow;
+        
+        if approver == escrow.buyer {
+            escrow.buyer_cancel_approval = None;
+        }
+        if approver == escrow.seller {
+            escrow.seller_cancel_approval = None;
+        }
+        
+        emit!(CancellationRevoked {
+            escrow_id: escrow.escrow_id,
+            approver,
+        });
+        
+        Ok(())
+    }
+
+    /// Close a settled escrow and its vault, returning the rent and any tokens
+    /// left in the vault to the buyer. A compact receipt is left behind and
+    /// reserves the escrow ID.
+    pub fn close_escrow<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseEscrow<'info>>,
+    ) -> Result<()> {
+        let escrow_account_info = ctx.accounts.escrow.to_account_info();
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.status.is_terminal(), EscrowError::InvalidEscrowState);
+        // Dispute accounts are keyed by the escrow, so their rent is reclaimed first
+        require!(escrow.open_disputes == 0, EscrowError::DisputesOpen);
+        
+        // Store immutable values to avoid borrow conflicts
+        let escrow_id = escrow.escrow_id;
+        let bump = escrow.bump;
+        
+        let seeds = &[
+            b"escrow",
+            escrow_id.as_ref(),
+            &[bump],
+        ];
+        let signer = &[&seeds[..]];
+        
+        // Native SOL and never-funded escrows have no vault; token escrows must close theirs too
+        require!(
+            escrow.is_native() || escrow.funded_at.is_none() || ctx.accounts.escrow_vault.is_some(),
+            EscrowError::MissingSettlementAccount
+        );
+        if let Some(escrow_vault) = ctx.accounts.escrow_vault.as_ref() {
+            let (Some(token_program), Some(token_mint)) = (
+                ctx.accounts.token_program.as_ref(),
+                ctx.accounts.token_mint.as_ref(),
+            ) else {
+                return err!(EscrowError::MissingSettlementAccount);
+            };
+            
+            // Anyone can send tokens to the vault, so a settled escrow may still hold some
+            let residual = vault_balance(&escrow_vault.to_account_info())?;
+            if residual > 0 {
+                let buyer_token_account = ctx.accounts
+                    .buyer_token_account
+                    .as_ref()
+                    .ok_or(EscrowError::MissingSettlementAccount)?;
+                let vault = VaultAccounts {
+                    token_program,
+                    escrow_vault,
+                    token_mint,
+                    hook_accounts: ctx.remaining_accounts,
+                };
+                vault.withdraw(
+                    buyer_token_account.to_account_info(),
+                    escrow_account_info.clone(),
+                    signer,
+                    residual,
+                )?;
+            }
+            
+            close_vault(
+                token_program,
+                escrow_vault,
+                token_mint,
+                ctx.accounts.buyer.to_account_info(),
+                escrow_account_info,
+                signer,
+            )?;
+        }
+        
+        // The receipt stays behind as a tombstone so the escrow ID can't be reused
+        let clock = Clock::get()?;
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.escrow_id = escrow_id;
+        receipt.buyer = escrow.buyer;
+        receipt.seller = escrow.seller;
+        receipt.token_mint = escrow.token_mint;
+        receipt.amount = escrow.amount;
+        receipt.fee_amount = escrow.fee_amount;
+        receipt.status = escrow.status.clone();
+        receipt.closed_at = clock.unix_timestamp;
+        receipt.bump = ctx.bumps.receipt;
+        
+        emit!(EscrowClosed {
+            escrow_id,
+            status: escrow.status.clone(),
+            closed_at: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Seller marks the current milestone delivered, which starts the buyer's
+    /// review window
+    pub fn submit_delivery(
+        ctx: Context<SubmitDelivery>,
+        milestone_index: u8,
+        evidence_hash: [u8; 32],
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.require_current_milestone(milestone_index)?;
+        require!(escrow.status == EscrowStatus::Funded, EscrowError::InvalidEscrowState);
+        require!(escrow.delivered_at.is_none(), EscrowError::AlreadyDelivered);
+        
+        let clock = Clock::get()?;
+        require!(
+            clock.unix_timestamp <= escrow.current_milestone()?.deadline,
+            EscrowError::DeliveryDeadlinePassed
+        );
+        
+        let auto_release_after = clock.unix_timestamp
+            .checked_add(escrow.review_window)
+            .ok_or(EscrowError::MathOverflow)?;
+        escrow.evidence_hash = Some(evidence_hash);
+        escrow.delivered_at = Some(clock.unix_timestamp);
+        escrow.auto_release_after = Some(auto_release_after);
+        let milestone = escrow.current_milestone_mut()?;
+        milestone.evidence_hash = Some(evidence_hash);
+        milestone.delivered_at = Some(clock.unix_timestamp);
+        
+        emit!(DeliverySubmitted {
+            escrow_id: escrow.escrow_id,
+            milestone_index,
+            evidence_hash,
+            auto_release_after,
+        });
         
-        escrow.status = EscrowStatus::Released;
         Ok(())
     }
 
-    /// Refund buyer if verification failed or escrow expired
-    pub fn refund_buyer(ctx: Context<RefundBuyer>) -> Result<()> {
+    /// Settle the current milestone once its timers run out. Anyone can crank
+    /// this: a delivery nobody disputed within the review window is released to
+    /// the seller, and a milestone with no delivery by its deadline is refunded.
+    pub fn auto_settle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AutoSettle<'info>>,
+        milestone_index: u8,
+    ) -> Result<()> {
         let escrow_account_info = ctx.accounts.escrow.to_account_info();
+        let is_native = ctx.accounts.escrow.is_native();
+        let vault = VaultAccounts::resolve(
+            is_native,
+            ctx.accounts.token_program.as_ref(),
+            ctx.accounts.escrow_vault.as_ref(),
+            ctx.accounts.token_mint.as_ref(),
+            ctx.remaining_accounts,
+        )?;
         let escrow = &mut ctx.accounts.escrow;
+        escrow.require_current_milestone(milestone_index)?;
+        require!(
+            escrow.status == EscrowStatus::Funded
+                || escrow.status == EscrowStatus::PendingVerification
+                || escrow.status == EscrowStatus::Verified,
+            EscrowError::InvalidEscrowState
+        );
+        require!(!escrow.dispute_raised, EscrowError::DisputeActive);
         
         let clock = Clock::get()?;
-        let can_refund = escrow.status == EscrowStatus::Funded && clock.unix_timestamp > escrow.expiry
-            || escrow.status == EscrowStatus::Verified && escrow.verification_result == Some(false)
-            || escrow.dispute_raised && escrow.dispute_deadline.is_some() 
-               && clock.unix_timestamp > escrow.dispute_deadline.unwrap();
-        
-        require!(can_refund, EscrowError::RefundNotAllowed);
+        let outcome = match escrow.auto_release_after {
+            // A failing verdict is refunded through refund_buyer instead
+            Some(auto_release_after)
+                if clock.unix_timestamp > auto_release_after
+                    && escrow.verification_result != Some(false) =>
+            {
+                DisputeOutcome::FavorSeller
+            },
+            // Only a milestone nobody delivered or verified refunds on the deadline
+            None if escrow.status == EscrowStatus::Funded
+                && escrow.verification_result.is_none()
+                && clock.unix_timestamp > escrow.current_milestone()?.deadline =>
+            {
+                DisputeOutcome::FavorBuyer
+            },
+            _ => return err!(EscrowError::AutoSettleNotReady),
+        };
+        let (buyer_amount, seller_amount, fee_amount) = escrow.settlement_amounts(&outcome)?;
         
         // Store immutable values to avoid borrow conflicts
-        let escrow_id = escrow.escrow_id.clone();
+        let escrow_id = escrow.escrow_id;
         let bump = escrow.bump;
-        let amount = escrow.amount;
         
         let seeds = &[
             b"escrow",
@@ -272,76 +1510,202 @@ pub mod aetherlock_escrow {
         ];
         let signer = &[&seeds[..]];
         
-        // Refund full amount to buyer
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.escrow_vault.to_account_info(),
-                    to: ctx.accounts.buyer_token_account.to_account_info(),
-                    authority: escrow_account_info.clone(),
-                },
+        let status = if outcome == DisputeOutcome::FavorSeller {
+            let seller_destination = settlement_destination(
+                is_native,
+                ctx.accounts.seller_token_account.as_ref(),
+                ctx.accounts.seller_wallet.as_ref(),
+            )?;
+            let treasury_destination = settlement_destination(
+                is_native,
+                ctx.accounts.protocol_treasury.as_ref(),
+                ctx.accounts.treasury_wallet.as_ref(),
+            )?;
+            
+            transfer_from_vault(
+                vault.as_ref(),
+                seller_destination,
+                escrow_account_info.clone(),
                 signer,
-            ),
-            amount,
-        )?;
+                seller_amount,
+            )?;
+            transfer_from_vault(
+                vault.as_ref(),
+                treasury_destination,
+                escrow_account_info,
+                signer,
+                fee_amount,
+            )?;
+            EscrowStatus::Released
+        } else {
+            let buyer_destination = settlement_destination(
+                is_native,
+                ctx.accounts.buyer_token_account.as_ref(),
+                ctx.accounts.buyer_wallet.as_ref(),
+            )?;
+            
+            transfer_from_vault(
+                vault.as_ref(),
+                buyer_destination,
+                escrow_account_info,
+                signer,
+                buyer_amount,
+            )?;
+            EscrowStatus::Refunded
+        };
+        
+        escrow.settle_current_milestone(status.clone())?;
+        
+        emit!(MilestoneSettled {
+            escrow_id,
+            milestone_index,
+            status,
+            buyer_amount,
+            seller_amount,
+            fee_amount,
+        });
         
-        escrow.status = EscrowStatus::Refunded;
         Ok(())
     }
 
-    /// Raise a dispute to pause automatic resolution
-    pub fn raise_dispute(
-        ctx: Context<RaiseDispute>,
-        _reason_hash: [u8; 32],
+    /// Raise a dispute over the current milestone to pause automatic resolution
+    /// and seat an arbitrator panel. The raiser posts the configured bond.
+    /// Registry entries of the registered agents with a verdict on the milestone
+    /// lead the remaining accounts, in verdict order, ahead of any transfer hook
+    /// accounts.
+    pub fn raise_dispute<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RaiseDispute<'info>>,
+        milestone_index: u8,
+        reason_hash: [u8; 32],
     ) -> Result<()> {
+        let dispute_account_info = ctx.accounts.dispute.to_account_info();
         let escrow = &mut ctx.accounts.escrow;
+        let config = &mut ctx.accounts.config;
+        let dispute = &mut ctx.accounts.dispute;
+        let evidence = &mut ctx.accounts.evidence;
+        escrow.require_current_milestone(milestone_index)?;
         require!(
-            escrow.status == EscrowStatus::Funded || escrow.status == EscrowStatus::Verified,
+            escrow.status == EscrowStatus::Funded
+                || escrow.status == EscrowStatus::PendingVerification
+                || escrow.status == EscrowStatus::Verified,
             EscrowError::InvalidEscrowState
         );
         require!(!escrow.dispute_raised, EscrowError::DisputeAlreadyRaised);
         
+        let contested_agents = escrow.registered_verdict_agents();
+        require!(
+            ctx.remaining_accounts.len() >= contested_agents.len(),
+            EscrowError::UnregisteredAgent
+        );
+        let (agent_entries, hook_accounts) =
+            ctx.remaining_accounts.split_at(contested_agents.len());
+        
         let clock = Clock::get()?;
-        let dispute_window = 48 * 60 * 60; // 48 hours in seconds
+        // Once a delivery's review window has passed it can only auto-release,
+        // unless a failing verdict left it to be refunded or contested
+        if let Some(auto_release_after) = escrow.auto_release_after {
+            require!(
+                clock.unix_timestamp <= auto_release_after
+                    || escrow.verification_result == Some(false),
+                EscrowError::ReviewWindowClosed
+            );
+        }
+        
+        // Token bonds are recorded net of any Token-2022 transfer fee
+        let bond_amount = config.dispute_bond.amount(escrow.amount)?;
+        let bond_amount = if bond_amount == 0 {
+            0
+        } else if escrow.is_native() {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.participant.to_account_info(),
+                        to: dispute_account_info,
+                    },
+                ),
+                bond_amount,
+            )?;
+            bond_amount
+        } else {
+            let bond_vault = VaultAccounts::resolve(
+                false,
+                ctx.accounts.token_program.as_ref(),
+                ctx.accounts.bond_vault.as_ref(),
+                ctx.accounts.token_mint.as_ref(),
+                hook_accounts,
+            )?
+            .ok_or(EscrowError::MissingSettlementAccount)?;
+            let raiser_token_account = ctx.accounts
+                .raiser_token_account
+                .as_ref()
+                .ok_or(EscrowError::MissingSettlementAccount)?;
+            
+            bond_vault.deposit(
+                raiser_token_account.to_account_info(),
+                ctx.accounts.participant.to_account_info(),
+                bond_amount,
+            )?;
+            vault_balance(&bond_vault.escrow_vault.to_account_info())?
+        };
         
-        escrow.dispute_raised = true;
-        escrow.dispute_deadline = Some(clock.unix_timestamp + dispute_window);
-        escrow.status = EscrowStatus::Disputed;
+        open_dispute(
+            escrow,
+            config,
+            dispute,
+            evidence,
+            ctx.accounts.participant.key(),
+            ctx.accounts.participant.key(),
+            clock.unix_timestamp,
+        )?;
+        // The agents' stakes stay held until the dispute is finalized
+        add_agent_disputes(&contested_agents, agent_entries)?;
+        dispute.bond_amount = bond_amount;
+        dispute.bump = ctx.bumps.dispute;
+        evidence.bump = ctx.bumps.evidence;
+        
+        // The stated reason opens the evidence trail
+        evidence.append(dispute.raised_by, reason_hash, String::new(), clock.unix_timestamp)?;
+        
+        emit!(DisputePanelSelected {
+            escrow_id: escrow.escrow_id,
+            milestone_index,
+            raised_by: dispute.raised_by,
+            panel: dispute.panel.clone(),
+            deadline: dispute.deadline,
+            bond_amount,
+        });
         
         Ok(())
     }
 
-    /// Admin function to resolve disputes manually
-    pub fn resolve_dispute(
-        ctx: Context<ResolveDispute>,
+    /// Arbitrator on the dispute panel votes for an outcome
+    pub fn cast_dispute_vote(
+        ctx: Context<CastDisputeVote>,
         outcome: DisputeOutcome,
     ) -> Result<()> {
-        let escrow = &mut ctx.accounts.escrow;
-        let config = &ctx.accounts.config;
-        
-        require!(escrow.status == EscrowStatus::Disputed, EscrowError::InvalidEscrowState);
-        
-        // Verify admin authorization
-        let admin_key = ctx.accounts.admin.key();
-        require!(
-            config.admin_pubkeys.contains(&admin_key),
-            EscrowError::UnauthorizedAdmin
-        );
-        
-        match outcome {
-            DisputeOutcome::FavorBuyer => {
-                escrow.verification_result = Some(false);
-            },
-            DisputeOutcome::FavorSeller => {
-                escrow.verification_result = Some(true);
-            },
-        }
-        
-        escrow.dispute_raised = false;
-        escrow.status = EscrowStatus::Verified;
-        
-        Ok(())
+        arbitration::cast_dispute_vote(ctx, outcome)
+    }
+
+    /// Finalize a dispute by panel vote and settle the vault
+    pub fn finalize_dispute<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FinalizeDispute<'info>>,
+    ) -> Result<()> {
+        arbitration::finalize_dispute(ctx)
+    }
+
+    /// Close a finalized dispute's accounts and return their rent
+    pub fn close_dispute(ctx: Context<CloseDispute>) -> Result<()> {
+        arbitration::close_dispute(ctx)
+    }
+
+    /// Buyer or seller adds evidence to an open dispute
+    pub fn submit_dispute_evidence(
+        ctx: Context<SubmitDisputeEvidence>,
+        content_hash: [u8; 32],
+        uri: String,
+    ) -> Result<()> {
+        evidence::submit_dispute_evidence(ctx, content_hash, uri)
     }
 
     /// Universal cross-chain functions
@@ -414,7 +1778,32 @@ pub struct InitializeEscrow<'info> {
         bump
     )]
     pub escrow: Account<'info, EscrowAccount>,
-    pub token_mint: Account<'info, Mint>,
+    /// Omitted for native SOL escrows
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    /// Allowlist entry for `token_mint` (or `NATIVE_SOL_MINT`)
+    #[account(
+        seeds = [b"supported_mint", supported_mint.mint.as_ref()],
+        bump = supported_mint.bump
+    )]
+    pub supported_mint: Account<'info, SupportedMint>,
+    /// Registry entry for `ai_agent_pubkey`, if the agent is registered
+    #[account(
+        seeds = [b"agent", ai_agent.agent.as_ref()],
+        bump = ai_agent.bump
+    )]
+    pub ai_agent: Option<Account<'info, AiAgent>>,
+    /// CHECK: Receipt `close_escrow` leaves for this escrow ID; must not exist
+    #[account(
+        seeds = [b"receipt", escrow_id.as_ref()],
+        bump,
+        constraint = receipt.data_is_empty() @ EscrowError::EscrowIdReused
+    )]
+    pub receipt: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
@@ -429,40 +1818,110 @@ pub struct DepositFunds<'info> {
         constraint = escrow.buyer == buyer.key()
     )]
     pub escrow: Account<'info, EscrowAccount>,
-    pub token_mint: Account<'info, Mint>,
+    #[account(address = escrow.token_mint)]
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
     #[account(
         init,
         payer = buyer,
         token::mint = token_mint,
         token::authority = escrow,
+        token::token_program = token_program,
         seeds = [b"vault", escrow.key().as_ref()],
         bump
     )]
-    pub escrow_vault: Account<'info, TokenAccount>,
+    pub escrow_vault: Option<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
-        constraint = buyer_token_account.mint == token_mint.key()
+        constraint = buyer_token_account.mint == escrow.token_mint
     )]
-    pub buyer_token_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    pub buyer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct SubmitVerification<'info> {
+pub struct SubmitDelivery<'info> {
+    pub seller: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.escrow_id.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.seller == seller.key()
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+}
+
+#[derive(Accounts)]
+pub struct AutoSettle<'info> {
+    pub caller: Signer<'info>,
     #[account(
         mut,
         seeds = [b"escrow", escrow.escrow_id.as_ref()],
         bump = escrow.bump
     )]
     pub escrow: Account<'info, EscrowAccount>,
-    /// CHECK: AI agent public key - verified against stored ai_agent_pubkey in escrow account
-    pub ai_agent: AccountInfo<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump
+    )]
+    pub escrow_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = escrow.token_mint)]
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        constraint = buyer_token_account.mint == escrow.token_mint,
+        constraint = buyer_token_account.owner == escrow.buyer
+    )]
+    pub buyer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = seller_token_account.mint == escrow.token_mint,
+        constraint = escrow.is_seller_payout_account(
+            seller_token_account.key(),
+            seller_token_account.owner
+        ) @ EscrowError::InvalidPayoutAccount
+    )]
+    pub seller_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = escrow.token_mint,
+        associated_token::authority = config.treasury_owner,
+        associated_token::token_program = token_program
+    )]
+    pub protocol_treasury: Option<InterfaceAccount<'info, TokenAccount>>,
+    /// Wallets paid on native SOL escrows
+    #[account(mut, address = escrow.buyer)]
+    pub buyer_wallet: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        address = escrow.seller_payout.unwrap_or(escrow.seller) @ EscrowError::InvalidPayoutAccount
+    )]
+    pub seller_wallet: Option<SystemAccount<'info>>,
+    #[account(mut, address = config.treasury_owner)]
+    pub treasury_wallet: Option<SystemAccount<'info>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptEscrow<'info> {
+    pub seller: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.escrow_id.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.seller == seller.key()
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
 }
 
 #[derive(Accounts)]
-pub struct ReleaseFunds<'info> {
-    #[account(mut)]
+pub struct CancelEscrow<'info> {
     pub buyer: Signer<'info>,
     #[account(
         mut,
@@ -471,23 +1930,183 @@ pub struct ReleaseFunds<'info> {
         constraint = escrow.buyer == buyer.key()
     )]
     pub escrow: Account<'info, EscrowAccount>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeCancellation<'info> {
+    pub approver: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.escrow_id.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.buyer == approver.key() || escrow.seller == approver.key()
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveCancellation<'info> {
+    pub approver: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.escrow_id.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.buyer == approver.key() || escrow.seller == approver.key()
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+    /// Refunded directly on native SOL escrows
+    #[account(mut, address = escrow.buyer)]
+    pub buyer: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump
+    )]
+    pub escrow_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = escrow.token_mint)]
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        constraint = buyer_token_account.mint == escrow.token_mint,
+        constraint = buyer_token_account.owner == escrow.buyer
+    )]
+    pub buyer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct SetSellerPayout<'info> {
+    pub seller: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.escrow_id.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.seller == seller.key()
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+    #[account(
+        constraint = payout_account.mint == escrow.token_mint
+    )]
+    pub payout_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub payout_wallet: Option<SystemAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct RequestVerification<'info> {
+    pub requester: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.escrow_id.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.buyer == requester.key() || escrow.seller == requester.key()
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitVerification<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.escrow_id.as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+    /// Registry entry of the agent, which must be one of the escrow's agents
+    #[account(
+        mut,
+        seeds = [b"agent", ai_agent.agent.as_ref()],
+        bump = ai_agent.bump
+    )]
+    pub ai_agent: Option<Account<'info, AiAgent>>,
+    /// CHECK: Key the verdict is signed with - verified against the agent's valid signing keys
+    pub signing_key: AccountInfo<'info>,
+    /// CHECK: Registry address of `signing_key`, passed instead of `ai_agent` by an
+    /// agent that never registered; it must be empty
+    #[account(
+        seeds = [b"agent", signing_key.key().as_ref()],
+        bump,
+        constraint = unregistered_agent.data_is_empty() @ EscrowError::AgentIsRegistered
+    )]
+    pub unregistered_agent: Option<UncheckedAccount<'info>>,
+    /// CHECK: Instructions sysvar - used to inspect the preceding Ed25519 program instruction
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    /// Opened if this verdict escalates the milestone to dispute
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Dispute::INIT_SPACE,
+        seeds = [b"dispute", escrow.key().as_ref(), &[escrow.current_milestone]],
+        bump
+    )]
+    pub dispute: Option<Account<'info, Dispute>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + DisputeEvidence::INIT_SPACE,
+        seeds = [b"evidence", dispute.as_ref().map(Key::key).unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub evidence: Option<Account<'info, DisputeEvidence>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseFunds<'info> {
+    pub caller: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.escrow_id.as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
     #[account(
         mut,
         seeds = [b"vault", escrow.key().as_ref()],
         bump
     )]
-    pub escrow_vault: Account<'info, TokenAccount>,
+    pub escrow_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = escrow.token_mint)]
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        constraint = seller_token_account.mint == escrow.token_mint,
+        constraint = escrow.is_seller_payout_account(
+            seller_token_account.key(),
+            seller_token_account.owner
+        ) @ EscrowError::InvalidPayoutAccount
+    )]
+    pub seller_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
-        constraint = seller_token_account.mint == escrow.token_mint
+        associated_token::mint = escrow.token_mint,
+        associated_token::authority = config.treasury_owner,
+        associated_token::token_program = token_program
     )]
-    pub seller_token_account: Account<'info, TokenAccount>,
+    pub protocol_treasury: Option<InterfaceAccount<'info, TokenAccount>>,
+    /// Seller wallet paid on native SOL escrows
     #[account(
         mut,
-        constraint = protocol_treasury.mint == escrow.token_mint
+        address = escrow.seller_payout.unwrap_or(escrow.seller) @ EscrowError::InvalidPayoutAccount
     )]
-    pub protocol_treasury: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    pub seller_wallet: Option<SystemAccount<'info>>,
+    /// Treasury wallet paid on native SOL escrows
+    #[account(mut, address = config.treasury_owner)]
+    pub treasury_wallet: Option<SystemAccount<'info>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
 }
 
 #[derive(Accounts)]
@@ -506,25 +2125,113 @@ pub struct RefundBuyer<'info> {
         seeds = [b"vault", escrow.key().as_ref()],
         bump
     )]
-    pub escrow_vault: Account<'info, TokenAccount>,
+    pub escrow_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = escrow.token_mint)]
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
     #[account(
         mut,
         constraint = buyer_token_account.mint == escrow.token_mint
     )]
-    pub buyer_token_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    pub buyer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseEscrow<'info> {
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.escrow_id.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.buyer == caller.key() || escrow.seller == caller.key(),
+        close = buyer
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+    /// Paid the escrow and vault rent, so gets it back
+    #[account(mut, address = escrow.buyer)]
+    pub buyer: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump
+    )]
+    pub escrow_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = escrow.token_mint)]
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
+    /// Receives any tokens left in the vault; only needed if there are some
+    #[account(
+        mut,
+        constraint = buyer_token_account.mint == escrow.token_mint,
+        constraint = buyer_token_account.owner == escrow.buyer
+    )]
+    pub buyer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = caller,
+        space = 8 + EscrowReceipt::INIT_SPACE,
+        seeds = [b"receipt", escrow.escrow_id.as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, EscrowReceipt>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 pub struct RaiseDispute<'info> {
+    #[account(mut)]
     pub participant: Signer<'info>,
     #[account(
         mut,
-        seeds = [b"escrow", escrow.escrow_id.as_ref()],
-        bump = escrow.bump,
-        constraint = escrow.buyer == participant.key() || escrow.seller == participant.key()
+        seeds = [b"escrow", escrow.escrow_id.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.buyer == participant.key() || escrow.seller == participant.key()
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+    #[account(
+        init,
+        payer = participant,
+        space = 8 + Dispute::INIT_SPACE,
+        seeds = [b"dispute", escrow.key().as_ref(), &[escrow.current_milestone]],
+        bump
+    )]
+    pub dispute: Account<'info, Dispute>,
+    #[account(
+        init,
+        payer = participant,
+        space = 8 + DisputeEvidence::INIT_SPACE,
+        seeds = [b"evidence", dispute.key().as_ref()],
+        bump
+    )]
+    pub evidence: Account<'info, DisputeEvidence>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(address = escrow.token_mint)]
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
+    /// Pays the bond on token escrows
+    #[account(
+        mut,
+        constraint = raiser_token_account.mint == escrow.token_mint,
+        constraint = raiser_token_account.owner == participant.key()
     )]
-    pub escrow: Account<'info, EscrowAccount>,
+    pub raiser_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = participant,
+        token::mint = token_mint,
+        token::authority = escrow,
+        token::token_program = token_program,
+        seeds = [b"bond", dispute.key().as_ref()],
+        bump
+    )]
+    pub bond_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -556,17 +2263,13 @@ pub struct UpdateConfig<'info> {
 }
 
 #[derive(Accounts)]
-pub struct ResolveDispute<'info> {
+pub struct ManageArbitrators<'info> {
     pub admin: Signer<'info>,
     #[account(
         mut,
-        seeds = [b"escrow", escrow.escrow_id.as_ref()],
-        bump = escrow.bump
-    )]
-    pub escrow: Account<'info, EscrowAccount>,
-    #[account(
         seeds = [b"config"],
-        bump = config.bump
+        bump = config.bump,
+        constraint = config.is_admin(&admin.key()) @ EscrowError::UnauthorizedAdmin
     )]
     pub config: Account<'info, ProtocolConfig>,
 }
@@ -604,9 +2307,83 @@ pub struct ProtocolConfig {
     pub authority: Pubkey,
     #[max_len(5)]
     pub admin_pubkeys: Vec<Pubkey>,
+    pub fee_bps: u16,
+    pub min_fee: u64,
+    pub max_fee: u64,
+    pub treasury_owner: Pubkey,
+    pub challenge_period: i64,
+    #[max_len(MAX_ARBITRATORS)]
+    pub arbitrators: Vec<Pubkey>,
+    pub panel_size: u8,
+    pub quorum: u8,
+    pub vote_threshold_bps: u16,
+    pub arbitration_cursor: u32,
+    pub default_resolution: DefaultResolutionPolicy,
+    pub dispute_window: WindowConfig,
+    pub evidence_window: WindowConfig,
+    pub dispute_bond: DisputeBond,
+    pub bond_forfeit: BondForfeitDestination,
+    /// Mint new agents stake in (`NATIVE_SOL_MINT` for SOL)
+    pub agent_stake_mint: Pubkey,
+    pub min_agent_stake: u64,
+    pub agent_slash_bps: u16,
+    /// How long after unbonding and its last verdict an agent must wait to withdraw stake
+    pub agent_unbonding_period: i64,
+    /// Whether new escrows must name a registered, active agent
+    pub require_registered_agent: bool,
+    /// Identifies the cluster in signed verification payloads
+    pub domain_separator: [u8; 32],
     pub bump: u8,
 }
 
+/// Default length of a time window, in seconds, and the bounds an escrow may pick from
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct WindowConfig {
+    pub default: i64,
+    pub min: i64,
+    pub max: i64,
+}
+
+impl WindowConfig {
+    pub fn is_valid(&self) -> bool {
+        0 < self.min && self.min <= self.default && self.default <= self.max
+    }
+
+    /// The window an escrow asked for, or the default, checked against the bounds
+    pub fn resolve(&self, requested: Option<i64>) -> Result<i64> {
+        let window = requested.unwrap_or(self.default);
+        require!(
+            (self.min..=self.max).contains(&window),
+            EscrowError::InvalidDisputeWindow
+        );
+        Ok(window)
+    }
+}
+
+impl ProtocolConfig {
+    /// Whether `key` is the config authority or one of its admins
+    pub fn is_admin(&self, key: &Pubkey) -> bool {
+        self.authority == *key || self.admin_pubkeys.contains(key)
+    }
+    
+    /// Protocol fee at `fee_bps` for an escrow of `amount`, clamped to the configured floor and cap
+    /// (a `max_fee` of zero means uncapped) and never more than the amount itself
+    pub fn calculate_fee(&self, amount: u64, fee_bps: u16) -> Result<u64> {
+        let mut fee = (amount as u128)
+            .checked_mul(fee_bps as u128)
+            .ok_or(EscrowError::MathOverflow)?
+            .checked_div(BPS_DENOMINATOR as u128)
+            .ok_or(EscrowError::MathOverflow)? as u64;
+        
+        fee = fee.max(self.min_fee);
+        if self.max_fee > 0 {
+            fee = fee.min(self.max_fee);
+        }
+        
+        Ok(fee.min(amount))
+    }
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct EscrowAccount {
@@ -616,18 +2393,336 @@ pub struct EscrowAccount {
     pub token_mint: Pubkey,
     pub amount: u64,
     pub fee_amount: u64,
+    pub fee_bps: u16,
     pub status: EscrowStatus,
-    pub expiry: i64,
+    pub delivery_deadline: i64,
+    pub review_window: i64,
+    pub delivered_at: Option<i64>,
+    pub auto_release_after: Option<i64>,
+    pub acceptance_deadline: i64,
+    pub dispute_window: i64,
+    pub evidence_window: i64,
+    pub accepted_at: Option<i64>,
     pub metadata_hash: [u8; 32],
     pub verification_result: Option<bool>,
     pub evidence_hash: Option<[u8; 32]>,
+    /// Passing verdicts below this confidence go to dispute (0 = never)
+    pub min_confidence_bps: u16,
+    /// Confidence, model and report behind `verification_result`
+    pub confidence_bps: Option<u16>,
+    pub model_hash: Option<[u8; 32]>,
+    pub report_hash: Option<[u8; 32]>,
+    /// Highest verification nonce each of `ai_agents` has used, in the same order;
+    /// an agent's later verdicts must carry a larger one
+    #[max_len(MAX_VERIFIER_AGENTS)]
+    pub agent_nonces: Vec<u64>,
     pub dispute_raised: bool,
     pub dispute_deadline: Option<i64>,
+    /// Disputes opened on this escrow whose accounts have not been closed yet
+    pub open_disputes: u8,
+    /// Registry identity of the primary agent
     pub ai_agent_pubkey: Pubkey,
+    /// Registry identities of the agents whose verdicts count, `ai_agent_pubkey` first
+    #[max_len(MAX_VERIFIER_AGENTS)]
+    pub ai_agents: Vec<Pubkey>,
+    /// Number of agreeing verdicts that decides a milestone
+    pub verdict_threshold: u8,
+    /// Verdicts submitted for the current verification request
+    #[max_len(MAX_VERIFIER_AGENTS)]
+    pub agent_verdicts: Vec<AgentVerdict>,
     pub chainlink_request_id: Option<[u8; 32]>,
+    pub chainlink_requested_at: Option<i64>,
+    pub seller_payout: Option<Pubkey>,
+    pub challenge_period: i64,
+    pub verified_at: Option<i64>,
+    pub funded_at: Option<i64>,
+    pub buyer_cancel_approval: Option<CancellationApproval>,
+    pub seller_cancel_approval: Option<CancellationApproval>,
+    #[max_len(MAX_MILESTONES)]
+    pub milestones: Vec<Milestone>,
+    pub current_milestone: u8,
     pub bump: u8,
 }
 
+/// Tranche of an escrow, verified and released on its own
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct Milestone {
+    pub amount: u64,
+    pub fee_amount: u64,
+    /// Delivery deadline for this tranche
+    pub deadline: i64,
+    pub delivered_at: Option<i64>,
+    pub evidence_hash: Option<[u8; 32]>,
+    pub verification_result: Option<bool>,
+    pub status: EscrowStatus,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct MilestoneParams {
+    pub amount: u64,
+    pub deadline: i64,
+}
+
+/// One agent's verdict on the current verification request
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct AgentVerdict {
+    pub agent: Pubkey,
+    /// Signed through a registry entry, so its stake can be slashed
+    pub registered: bool,
+    pub result: bool,
+    pub confidence_bps: u16,
+    pub evidence_hash: [u8; 32],
+    pub model_hash: [u8; 32],
+    pub report_hash: [u8; 32],
+}
+
+impl EscrowAccount {
+    /// Whether the escrow holds native SOL rather than an SPL token
+    pub fn is_native(&self) -> bool {
+        self.token_mint == NATIVE_SOL_MINT
+    }
+
+    /// Rescale the escrow to what the vault actually received, e.g. net of a
+    /// Token-2022 transfer fee. Each tranche and its fee shrink pro rata, with
+    /// the rounding remainder going to the last tranche.
+    pub fn record_deposit(&mut self, received: u64) -> Result<()> {
+        let expected = self.amount;
+        require!(received <= expected, EscrowError::MathOverflow);
+        if received == expected {
+            return Ok(());
+        }
+        
+        let scale = |value: u64| -> Result<u64> {
+            Ok((value as u128)
+                .checked_mul(received as u128)
+                .ok_or(EscrowError::MathOverflow)?
+                .checked_div(expected as u128)
+                .ok_or(EscrowError::MathOverflow)? as u64)
+        };
+        
+        let mut remaining = received;
+        let mut fee_amount: u64 = 0;
+        let last = self.milestones.len().saturating_sub(1);
+        for (index, milestone) in self.milestones.iter_mut().enumerate() {
+            let amount = if index == last { remaining } else { scale(milestone.amount)? };
+            remaining = remaining.checked_sub(amount).ok_or(EscrowError::MathOverflow)?;
+            milestone.fee_amount = scale(milestone.fee_amount)?.min(amount);
+            milestone.amount = amount;
+            fee_amount = fee_amount
+                .checked_add(milestone.fee_amount)
+                .ok_or(EscrowError::MathOverflow)?;
+        }
+        
+        self.amount = received;
+        self.fee_amount = fee_amount;
+        Ok(())
+    }
+
+    /// Tally the current round's verdicts after one for `result` was added, and
+    /// decide whether the round must escalate to a dispute. Neither side had
+    /// reached the threshold before that verdict, so if one has now, it decides
+    /// the outcome, carrying the lowest confidence among the agents that agree.
+    pub fn tally_verdicts(&self, result: bool) -> (VerdictTally, Option<EscalationReason>) {
+        let verdicts_for = self.agent_verdicts.iter().filter(|verdict| verdict.result).count();
+        let verdicts_against = self.agent_verdicts.len() - verdicts_for;
+        let outstanding = self.ai_agents.len().saturating_sub(self.agent_verdicts.len());
+        let threshold = self.verdict_threshold as usize;
+        
+        let decided = verdicts_for >= threshold || verdicts_against >= threshold;
+        let confidence_bps = self
+            .agent_verdicts
+            .iter()
+            .filter(|verdict| verdict.result == result)
+            .map(|verdict| verdict.confidence_bps)
+            .min()
+            .unwrap_or(0);
+        let escalation = if decided && result && confidence_bps < self.min_confidence_bps {
+            Some(EscalationReason::LowConfidence)
+        } else if !decided
+            && verdicts_for + outstanding < threshold
+            && verdicts_against + outstanding < threshold
+        {
+            Some(EscalationReason::SplitVerdict)
+        } else {
+            None
+        };
+        
+        let tally = VerdictTally {
+            verdicts_for: verdicts_for as u8,
+            verdicts_against: verdicts_against as u8,
+            decided,
+            confidence_bps,
+        };
+        (tally, escalation)
+    }
+
+    /// Registered agents with a verdict on the current milestone, whose stakes a
+    /// dispute over it holds
+    pub fn registered_verdict_agents(&self) -> Vec<Pubkey> {
+        self.agent_verdicts
+            .iter()
+            .filter(|verdict| verdict.registered)
+            .map(|verdict| verdict.agent)
+            .collect()
+    }
+
+    /// The milestone currently being verified, disputed or settled
+    pub fn current_milestone(&self) -> Result<&Milestone> {
+        self.milestones
+            .get(self.current_milestone as usize)
+            .ok_or_else(|| error!(EscrowError::MilestoneMismatch))
+    }
+
+    pub fn current_milestone_mut(&mut self) -> Result<&mut Milestone> {
+        self.milestones
+            .get_mut(self.current_milestone as usize)
+            .ok_or_else(|| error!(EscrowError::MilestoneMismatch))
+    }
+
+    /// Guard against acting on a milestone other than the one in progress
+    pub fn require_current_milestone(&self, milestone_index: u8) -> Result<()> {
+        require!(
+            milestone_index == self.current_milestone
+                && (milestone_index as usize) < self.milestones.len(),
+            EscrowError::MilestoneMismatch
+        );
+        Ok(())
+    }
+
+    /// Move the escrow and its current milestone to `status`, dropping any
+    /// cancellation approvals given in the previous state
+    pub fn set_status(&mut self, status: EscrowStatus) {
+        if let Some(milestone) = self.milestones.get_mut(self.current_milestone as usize) {
+            milestone.status = status.clone();
+        }
+        self.status = status;
+        self.buyer_cancel_approval = None;
+        self.seller_cancel_approval = None;
+    }
+    
+    /// The milestone and status a cancellation approval given now applies to
+    pub fn cancellation_approval(&self) -> CancellationApproval {
+        CancellationApproval {
+            milestone_index: self.current_milestone,
+            status: self.status.clone(),
+        }
+    }
+
+    /// Close out the current milestone with `status` and move on to the next.
+    /// The escrow returns to Funded while tranches remain, otherwise it takes
+    /// the status of its final tranche.
+    pub fn settle_current_milestone(&mut self, status: EscrowStatus) -> Result<()> {
+        let verification_result = self.verification_result;
+        let evidence_hash = self.evidence_hash;
+        let milestone = self.current_milestone_mut()?;
+        milestone.verification_result = verification_result;
+        milestone.evidence_hash = evidence_hash;
+        milestone.status = status.clone();
+        self.buyer_cancel_approval = None;
+        self.seller_cancel_approval = None;
+        
+        self.current_milestone += 1;
+        if (self.current_milestone as usize) < self.milestones.len() {
+            // Per-milestone working state starts fresh for the next tranche
+            self.verification_result = None;
+            self.evidence_hash = None;
+            self.confidence_bps = None;
+            self.model_hash = None;
+            self.report_hash = None;
+            self.agent_verdicts.clear();
+            self.dispute_raised = false;
+            self.dispute_deadline = None;
+            self.chainlink_request_id = None;
+            self.chainlink_requested_at = None;
+            self.verified_at = None;
+            self.delivered_at = None;
+            self.auto_release_after = None;
+            self.status = EscrowStatus::Funded;
+        } else {
+            self.status = status;
+        }
+        
+        Ok(())
+    }
+
+    /// Whether the outstanding Chainlink request has been pending longer than the timeout
+    pub fn chainlink_request_timed_out(&self, now: i64) -> bool {
+        match self.chainlink_requested_at {
+            Some(requested_at) => now > requested_at + CHAINLINK_REQUEST_TIMEOUT,
+            None => true,
+        }
+    }
+
+    /// Whether the challenge period after a verdict has passed
+    pub fn challenge_period_elapsed(&self, now: i64) -> bool {
+        match self.verified_at {
+            Some(verified_at) => now >= verified_at + self.challenge_period,
+            None => false,
+        }
+    }
+
+    /// Whether someone other than the buyer may release the verified tranche:
+    /// the challenge period must have passed, and so must the review window
+    /// if a delivery or pass started one
+    pub fn release_unlocked(&self, now: i64) -> bool {
+        self.challenge_period_elapsed(now)
+            && self.auto_release_after.is_none_or(|auto_release_after| now > auto_release_after)
+    }
+
+    /// Split the current milestone's tranche for a dispute outcome into
+    /// (buyer refund, seller payout, protocol fee). The fee is prorated
+    /// on the seller's share, so a full refund carries no fee.
+    pub fn settlement_amounts(&self, outcome: &DisputeOutcome) -> Result<(u64, u64, u64)> {
+        let milestone = self.current_milestone()?;
+        let amount = milestone.amount;
+        let buyer_bps = match outcome {
+            DisputeOutcome::FavorBuyer => BPS_DENOMINATOR as u16,
+            DisputeOutcome::FavorSeller => 0,
+            DisputeOutcome::Split { buyer_bps } => *buyer_bps,
+        };
+        require!(buyer_bps as u64 <= BPS_DENOMINATOR, EscrowError::InvalidSplit);
+        
+        let buyer_amount = (amount as u128)
+            .checked_mul(buyer_bps as u128)
+            .ok_or(EscrowError::MathOverflow)?
+            .checked_div(BPS_DENOMINATOR as u128)
+            .ok_or(EscrowError::MathOverflow)? as u64;
+        let seller_share = amount
+            .checked_sub(buyer_amount)
+            .ok_or(EscrowError::MathOverflow)?;
+        let fee_amount = if amount == 0 {
+            0
+        } else {
+            (milestone.fee_amount as u128)
+                .checked_mul(seller_share as u128)
+                .ok_or(EscrowError::MathOverflow)?
+                .checked_div(amount as u128)
+                .ok_or(EscrowError::MathOverflow)? as u64
+        };
+        let seller_amount = seller_share
+            .checked_sub(fee_amount)
+            .ok_or(EscrowError::MathOverflow)?;
+        
+        Ok((buyer_amount, seller_amount, fee_amount))
+    }
+
+    /// Whether a token account may receive the seller's payout: the alternate
+    /// payout account if the seller set one, otherwise any account the seller owns
+    pub fn is_seller_payout_account(&self, key: Pubkey, owner: Pubkey) -> bool {
+        match self.seller_payout {
+            Some(payout) => key == payout,
+            None => owner == self.seller,
+        }
+    }
+}
+
+/// Where the escrow stood when a party approved cancelling it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct CancellationApproval {
+    pub milestone_index: u8,
+    pub status: EscrowStatus,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
 pub enum EscrowStatus {
     Created,
@@ -637,12 +2732,44 @@ pub enum EscrowStatus {
     Disputed,
     Released,
     Refunded,
+    Resolved,
+    Cancelled,
+    Accepted,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+/// Compact record of a closed escrow, kept for history
+#[account]
+#[derive(InitSpace)]
+pub struct EscrowReceipt {
+    pub escrow_id: [u8; 32],
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub fee_amount: u64,
+    pub status: EscrowStatus,
+    pub closed_at: i64,
+    pub bump: u8,
+}
+
+impl EscrowStatus {
+    /// Whether the escrow has been settled and its vault paid out
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            EscrowStatus::Released
+                | EscrowStatus::Refunded
+                | EscrowStatus::Resolved
+                | EscrowStatus::Cancelled
+        )
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace, Debug)]
 pub enum DisputeOutcome {
     FavorBuyer,
     FavorSeller,
+    Split { buyer_bps: u16 },
 }
 
 #[error_code]
@@ -675,18 +2802,595 @@ pub enum EscrowError {
     ZkmeVerificationRequired,
     #[msg("Invalid Chainlink request ID")]
     InvalidChainlinkRequest,
+    #[msg("Chainlink request still pending - wait for timeout")]
+    ChainlinkRequestPending,
+    #[msg("Chainlink request expired - re-issue or cancel it")]
+    ChainlinkRequestExpired,
+    #[msg("Invalid fee configuration")]
+    InvalidFeeConfig,
+    #[msg("Payout account does not belong to the seller")]
+    InvalidPayoutAccount,
+    #[msg("Invalid challenge period")]
+    InvalidChallengePeriod,
+    #[msg("Challenge period still active - only the buyer can release")]
+    ChallengePeriodActive,
+    #[msg("Invalid split - buyer share must be at most 10000 bps")]
+    InvalidSplit,
+    #[msg("Too many arbitrators - maximum 10 allowed")]
+    TooManyArbitrators,
+    #[msg("Arbitrator already exists in the pool")]
+    ArbitratorAlreadyExists,
+    #[msg("Invalid arbitration configuration")]
+    InvalidArbitrationConfig,
+    #[msg("Not enough eligible arbitrators to seat a panel")]
+    NotEnoughArbitrators,
+    #[msg("Signer is not on this dispute's panel")]
+    NotOnPanel,
+    #[msg("Arbitrator has already voted on this dispute")]
+    AlreadyVoted,
+    #[msg("Dispute voting has closed")]
+    DisputeVotingClosed,
+    #[msg("Dispute has already been finalized")]
+    DisputeAlreadyFinalized,
+    #[msg("Quorum not reached and voting still open")]
+    QuorumNotReached,
+    #[msg("No outcome reached the required share of votes")]
+    DisputeUndecided,
+    #[msg("Evidence window has closed")]
+    EvidenceWindowClosed,
+    #[msg("Evidence limit reached for this submitter")]
+    EvidenceLimitReached,
+    #[msg("Evidence URI too long")]
+    EvidenceUriTooLong,
+    #[msg("Invalid milestones - amounts must sum to the escrow amount with ordered deadlines")]
+    InvalidMilestones,
+    #[msg("Milestone is not the one currently in progress")]
+    MilestoneMismatch,
+    #[msg("Token or wallet account required to settle this escrow is missing")]
+    MissingSettlementAccount,
+    #[msg("Mints with a permanent delegate are not supported")]
+    PermanentDelegateMint,
+    #[msg("Non-transferable mints are not supported")]
+    NonTransferableMint,
+    #[msg("Mints with confidential transfers are not supported")]
+    ConfidentialTransferMint,
+    #[msg("Pausable mints are not supported")]
+    PausableMint,
+    #[msg("Mints that freeze new accounts by default are not supported")]
+    FrozenByDefaultMint,
+    #[msg("Mint is not on the allowlist or has been disabled")]
+    UnsupportedMint,
+    #[msg("Escrow amount is outside the limits for this mint")]
+    EscrowAmountOutOfRange,
+    #[msg("Invalid mint config - min amount exceeds max amount")]
+    InvalidMintConfig,
+    #[msg("Vault still holds tokens")]
+    VaultNotEmpty,
+    #[msg("Acceptance deadline must not be after the delivery deadline")]
+    InvalidAcceptanceDeadline,
+    #[msg("Accepted terms do not match the escrow metadata hash")]
+    TermsMismatch,
+    #[msg("Acceptance deadline has passed")]
+    AcceptanceExpired,
+    #[msg("Invalid review window")]
+    InvalidReviewWindow,
+    #[msg("Milestone has already been delivered")]
+    AlreadyDelivered,
+    #[msg("Delivery deadline has passed")]
+    DeliveryDeadlinePassed,
+    #[msg("Neither the review window nor the delivery deadline has passed")]
+    AutoSettleNotReady,
+    #[msg("Review window has closed")]
+    ReviewWindowClosed,
+    #[msg("Dispute or evidence window is outside the allowed bounds")]
+    InvalidDisputeWindow,
+    #[msg("Dispute bond must not exceed 100% of the disputed amount")]
+    InvalidDisputeBond,
+    #[msg("Agent stake is below the protocol minimum")]
+    InsufficientAgentStake,
+    #[msg("Only the agent's operator can perform this action")]
+    UnauthorizedAgentOperator,
+    #[msg("No overturned verdict to slash this agent for")]
+    AgentNotSlashable,
+    #[msg("AI agent is not registered")]
+    UnregisteredAgent,
+    #[msg("AI agent is suspended")]
+    AgentSuspended,
+    #[msg("Agent slash share must not exceed 100%")]
+    InvalidAgentConfig,
+    #[msg("Verdict threshold must be between 1 and the number of distinct agents")]
+    InvalidAgentQuorum,
+    #[msg("AI agent has already submitted a verdict for this request")]
+    AgentAlreadyVoted,
+    #[msg("Dispute accounts must be passed exactly when a verdict escalates the milestone")]
+    DisputeAccountsMismatch,
+    #[msg("Unsupported verification payload version")]
+    UnsupportedPayloadVersion,
+    #[msg("Confidence must not exceed 10000 basis points")]
+    InvalidConfidence,
+    #[msg("Verification nonce has already been consumed")]
+    VerificationReplayed,
+    #[msg("Signing key is already registered or its validity window is invalid")]
+    InvalidSigningKey,
+    #[msg("Agent already holds the maximum number of signing keys")]
+    TooManySigningKeys,
+    #[msg("Signing key is not currently valid for this agent")]
+    UnknownSigningKey,
+    #[msg("Escrow ID belongs to a closed escrow")]
+    EscrowIdReused,
+    #[msg("Close the escrow's dispute accounts first")]
+    DisputesOpen,
+    #[msg("Dispute is not finalized or still has agents to slash")]
+    DisputeNotClosable,
+    #[msg("Agent is unbonding")]
+    AgentUnbonding,
+    #[msg("Agent stake is still bonded")]
+    AgentNotUnbonded,
+    #[msg("Agent is registered; pass its registry entry")]
+    AgentIsRegistered,
+    #[msg("Disputes over the agent's verdicts are still open")]
+    AgentDisputesOpen,
 }
 
 #[event]
 pub struct ChainlinkVerificationRequested {
     pub escrow_id: [u8; 32],
+    pub milestone_index: u8,
     pub evidence_hash: [u8; 32],
     pub request_id: [u8; 32],
+    pub previous_request_id: Option<[u8; 32]>,
+    pub expires_at: i64,
 }
 
 #[event]
 pub struct ChainlinkVerificationCompleted {
     pub escrow_id: [u8; 32],
+    pub milestone_index: u8,
     pub result: bool,
+    pub confidence_bps: u16,
+    pub model_hash: [u8; 32],
+    pub report_hash: [u8; 32],
+    pub request_id: [u8; 32],
+}
+
+#[event]
+pub struct ChainlinkVerificationCancelled {
+    pub escrow_id: [u8; 32],
+    pub milestone_index: u8,
     pub request_id: [u8; 32],
-}
\ No newline at end of file
+}
+
+#[event]
+pub struct FeeUpdated {
+    pub old_fee_bps: u16,
+    pub fee_bps: u16,
+    pub min_fee: u64,
+    pub max_fee: u64,
+}
+
+#[event]
+pub struct TreasuryUpdated {
+    pub old_treasury_owner: Pubkey,
+    pub new_treasury_owner: Pubkey,
+}
+
+#[event]
+pub struct SellerPayoutUpdated {
+    pub escrow_id: [u8; 32],
+    pub payout: Option<Pubkey>,
+}
+
+#[event]
+pub struct ChallengePeriodUpdated {
+    pub old_challenge_period: i64,
+    pub challenge_period: i64,
+}
+
+#[event]
+pub struct DisputeResolved {
+    pub escrow_id: [u8; 32],
+    pub milestone_index: u8,
+    pub outcome: DisputeOutcome,
+    pub votes_for: u8,
+    pub votes_cast: u8,
+    pub buyer_amount: u64,
+    pub seller_amount: u64,
+    pub fee_amount: u64,
+}
+
+#[event]
+pub struct MilestoneSettled {
+    pub escrow_id: [u8; 32],
+    pub milestone_index: u8,
+    pub status: EscrowStatus,
+    pub buyer_amount: u64,
+    pub seller_amount: u64,
+    pub fee_amount: u64,
+}
+
+#[event]
+pub struct EscrowClosed {
+    pub escrow_id: [u8; 32],
+    pub status: EscrowStatus,
+    pub closed_at: i64,
+}
+
+#[event]
+pub struct CancellationApproved {
+    pub escrow_id: [u8; 32],
+    pub approver: Pubkey,
+    pub milestone_index: u8,
+}
+
+#[event]
+pub struct CancellationRevoked {
+    pub escrow_id: [u8; 32],
+    pub approver: Pubkey,
+}
+
+#[event]
+pub struct EscrowCancelled {
+    pub escrow_id: [u8; 32],
+    pub refunded_amount: u64,
+}
+
+#[event]
+pub struct EscrowAccepted {
+    pub escrow_id: [u8; 32],
+    pub seller: Pubkey,
+    pub metadata_hash: [u8; 32],
+}
+
+#[event]
+pub struct DeliverySubmitted {
+    pub escrow_id: [u8; 32],
+    pub milestone_index: u8,
+    pub evidence_hash: [u8; 32],
+    pub auto_release_after: i64,
+}
+
+#[event]
+pub struct DisputeWindowsUpdated {
+    pub dispute_window: WindowConfig,
+    pub evidence_window: WindowConfig,
+}
+
+#[event]
+pub struct AgentVerdictSubmitted {
+    pub escrow_id: [u8; 32],
+    pub milestone_index: u8,
+    pub agent: Pubkey,
+    pub result: bool,
+    pub confidence_bps: u16,
+    pub verdicts_for: u8,
+    pub verdicts_against: u8,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(fee_bps: u16, min_fee: u64, max_fee: u64) -> ProtocolConfig {
+        let window = WindowConfig { default: 0, min: 0, max: 0 };
+        ProtocolConfig {
+            authority: Pubkey::default(),
+            admin_pubkeys: Vec::new(),
+            fee_bps,
+            min_fee,
+            max_fee,
+            treasury_owner: Pubkey::default(),
+            challenge_period: DEFAULT_CHALLENGE_PERIOD,
+            arbitrators: Vec::new(),
+            panel_size: DEFAULT_PANEL_SIZE,
+            quorum: DEFAULT_QUORUM,
+            vote_threshold_bps: DEFAULT_VOTE_THRESHOLD_BPS,
+            arbitration_cursor: 0,
+            default_resolution: DefaultResolutionPolicy::FollowAiVerdict,
+            dispute_window: window,
+            evidence_window: window,
+            dispute_bond: DisputeBond::None,
+            bond_forfeit: BondForfeitDestination::Counterparty,
+            agent_stake_mint: NATIVE_SOL_MINT,
+            min_agent_stake: 0,
+            agent_slash_bps: DEFAULT_AGENT_SLASH_BPS,
+            agent_unbonding_period: MIN_AGENT_UNBONDING_PERIOD,
+            require_registered_agent: false,
+            domain_separator: [0; 32],
+            bump: 0,
+        }
+    }
+
+    /// Funded escrow with one tranche per `(amount, fee_amount)`
+    fn escrow(tranches: &[(u64, u64)]) -> EscrowAccount {
+        let milestones: Vec<Milestone> = tranches
+            .iter()
+            .map(|&(amount, fee_amount)| Milestone {
+                amount,
+                fee_amount,
+                deadline: 0,
+                delivered_at: None,
+                evidence_hash: None,
+                verification_result: None,
+                status: EscrowStatus::Funded,
+            })
+            .collect();
+        EscrowAccount {
+            escrow_id: [1; 32],
+            buyer: Pubkey::new_unique(),
+            seller: Pubkey::new_unique(),
+            token_mint: Pubkey::new_unique(),
+            amount: milestones.iter().map(|milestone| milestone.amount).sum(),
+            fee_amount: milestones.iter().map(|milestone| milestone.fee_amount).sum(),
+            fee_bps: DEFAULT_FEE_BPS,
+            status: EscrowStatus::Funded,
+            delivery_deadline: 0,
+            review_window: 0,
+            delivered_at: None,
+            auto_release_after: None,
+            acceptance_deadline: 0,
+            dispute_window: 0,
+            evidence_window: 0,
+            accepted_at: None,
+            metadata_hash: [0; 32],
+            verification_result: None,
+            evidence_hash: None,
+            min_confidence_bps: 0,
+            confidence_bps: None,
+            model_hash: None,
+            report_hash: None,
+            agent_nonces: vec![0],
+            dispute_raised: false,
+            dispute_deadline: None,
+            open_disputes: 0,
+            ai_agent_pubkey: Pubkey::default(),
+            ai_agents: vec![Pubkey::default()],
+            verdict_threshold: 1,
+            agent_verdicts: Vec::new(),
+            chainlink_request_id: None,
+            chainlink_requested_at: None,
+            seller_payout: None,
+            challenge_period: 0,
+            verified_at: None,
+            funded_at: None,
+            buyer_cancel_approval: None,
+            seller_cancel_approval: None,
+            milestones,
+            current_milestone: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn calculate_fee_applies_rate() {
+        assert_eq!(config(1_000, 0, 0).calculate_fee(10_000, 1_000).unwrap(), 1_000);
+        assert_eq!(config(1_000, 0, 0).calculate_fee(10_000, 250).unwrap(), 250);
+        assert_eq!(config(1_000, 0, 0).calculate_fee(10_000, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn calculate_fee_clamps_to_floor_and_cap() {
+        assert_eq!(config(1_000, 50, 0).calculate_fee(100, 1_000).unwrap(), 50);
+        assert_eq!(config(1_000, 0, 500).calculate_fee(10_000, 1_000).unwrap(), 500);
+        // A zero cap means uncapped
+        assert_eq!(config(1_000, 0, 0).calculate_fee(u64::MAX, 1_000).unwrap(), u64::MAX / 10);
+    }
+
+    #[test]
+    fn calculate_fee_never_exceeds_amount() {
+        assert_eq!(config(1_000, 50, 0).calculate_fee(20, 1_000).unwrap(), 20);
+    }
+
+    #[test]
+    fn calculate_fee_uses_mint_override() {
+        let config = config(1_000, 0, 0);
+        let mut supported_mint = SupportedMint {
+            mint: Pubkey::new_unique(),
+            enabled: true,
+            min_amount: 0,
+            max_amount: 0,
+            fee_bps: None,
+            bump: 0,
+        };
+        let fee_bps = supported_mint.escrow_fee_bps(&config);
+        assert_eq!(config.calculate_fee(10_000, fee_bps).unwrap(), 1_000);
+        
+        supported_mint.fee_bps = Some(250);
+        let fee_bps = supported_mint.escrow_fee_bps(&config);
+        assert_eq!(config.calculate_fee(10_000, fee_bps).unwrap(), 250);
+    }
+
+    #[test]
+    fn settlement_amounts_favor_buyer_refunds_without_fee() {
+        let escrow = escrow(&[(1_000, 100)]);
+        assert_eq!(
+            escrow.settlement_amounts(&DisputeOutcome::FavorBuyer).unwrap(),
+            (1_000, 0, 0)
+        );
+    }
+
+    #[test]
+    fn settlement_amounts_favor_seller_charges_full_fee() {
+        let escrow = escrow(&[(1_000, 100)]);
+        assert_eq!(
+            escrow.settlement_amounts(&DisputeOutcome::FavorSeller).unwrap(),
+            (0, 900, 100)
+        );
+    }
+
+    #[test]
+    fn settlement_amounts_split_prorates_fee_on_seller_share() {
+        let escrow = escrow(&[(1_000, 100)]);
+        assert_eq!(
+            escrow
+                .settlement_amounts(&DisputeOutcome::Split { buyer_bps: 5_000 })
+                .unwrap(),
+            (500, 450, 50)
+        );
+        assert_eq!(
+            escrow
+                .settlement_amounts(&DisputeOutcome::Split { buyer_bps: 2_500 })
+                .unwrap(),
+            (250, 675, 75)
+        );
+        assert_eq!(
+            escrow
+                .settlement_amounts(&DisputeOutcome::Split { buyer_bps: 10_000 })
+                .unwrap(),
+            (1_000, 0, 0)
+        );
+    }
+
+    #[test]
+    fn settlement_amounts_rejects_split_above_full_amount() {
+        let escrow = escrow(&[(1_000, 100)]);
+        assert!(escrow
+            .settlement_amounts(&DisputeOutcome::Split { buyer_bps: 10_001 })
+            .is_err());
+    }
+
+    #[test]
+    fn settlement_amounts_uses_current_milestone() {
+        let mut escrow = escrow(&[(1_000, 100), (3_000, 300)]);
+        escrow.current_milestone = 1;
+        assert_eq!(
+            escrow.settlement_amounts(&DisputeOutcome::FavorSeller).unwrap(),
+            (0, 2_700, 300)
+        );
+    }
+
+    #[test]
+    fn record_deposit_keeps_full_deposit() {
+        let mut escrow = escrow(&[(600, 60), (400, 40)]);
+        escrow.record_deposit(1_000).unwrap();
+        assert_eq!((escrow.amount, escrow.fee_amount), (1_000, 100));
+        assert_eq!(escrow.milestones[1].amount, 400);
+    }
+
+    #[test]
+    fn record_deposit_rescales_after_transfer_fee() {
+        let mut escrow = escrow(&[(600, 60), (400, 40)]);
+        escrow.record_deposit(990).unwrap();
+        
+        assert_eq!(escrow.amount, 990);
+        assert_eq!(escrow.milestones[0].amount, 594);
+        // The rounding remainder lands on the last tranche
+        assert_eq!(escrow.milestones[1].amount, 396);
+        assert_eq!(escrow.milestones[0].fee_amount, 59);
+        assert_eq!(escrow.milestones[1].fee_amount, 39);
+        assert_eq!(escrow.fee_amount, 98);
+    }
+
+    #[test]
+    fn record_deposit_rejects_more_than_expected() {
+        let mut escrow = escrow(&[(1_000, 100)]);
+        assert!(escrow.record_deposit(1_001).is_err());
+    }
+
+    #[test]
+    fn release_unlocked_waits_for_challenge_period_and_review_window() {
+        let mut escrow = escrow(&[(1_000, 100)]);
+        escrow.challenge_period = 100;
+        escrow.verified_at = Some(1_000);
+        assert!(!escrow.release_unlocked(1_099));
+        assert!(escrow.release_unlocked(1_100));
+        
+        escrow.auto_release_after = Some(1_500);
+        assert!(!escrow.release_unlocked(1_500));
+        assert!(escrow.release_unlocked(1_501));
+    }
+
+    #[test]
+    fn registered_verdict_agents_skips_unregistered() {
+        let mut escrow = escrow(&[(1_000, 100)]);
+        let registered = Pubkey::new_unique();
+        for (agent, is_registered) in [(registered, true), (Pubkey::new_unique(), false)] {
+            escrow.agent_verdicts.push(AgentVerdict {
+                agent,
+                registered: is_registered,
+                result: true,
+                confidence_bps: 9_000,
+                evidence_hash: [0; 32],
+                model_hash: [0; 32],
+                report_hash: [0; 32],
+            });
+        }
+        assert_eq!(escrow.registered_verdict_agents(), vec![registered]);
+    }
+
+    const EVEN_SPLIT: [(bool, u16); 4] =
+        [(true, 9_000), (false, 9_000), (true, 9_000), (false, 9_000)];
+
+    /// Escrow verified by `agents` agents that have cast `verdicts` as (result, confidence)
+    fn voted_escrow(agents: usize, threshold: u8, verdicts: &[(bool, u16)]) -> EscrowAccount {
+        let mut escrow = escrow(&[(1_000, 100)]);
+        escrow.ai_agents = (0..agents).map(|_| Pubkey::new_unique()).collect();
+        escrow.verdict_threshold = threshold;
+        escrow.min_confidence_bps = 7_000;
+        escrow.agent_verdicts = escrow
+            .ai_agents
+            .iter()
+            .zip(verdicts)
+            .map(|(&agent, &(result, confidence_bps))| AgentVerdict {
+                agent,
+                registered: false,
+                result,
+                confidence_bps,
+                evidence_hash: [0; 32],
+                model_hash: [0; 32],
+                report_hash: [0; 32],
+            })
+            .collect();
+        escrow
+    }
+
+    #[test]
+    fn tally_verdicts_decides_two_of_three() {
+        let escrow = voted_escrow(3, 2, &[(true, 9_000), (false, 8_000), (true, 8_500)]);
+        let (tally, escalation) = escrow.tally_verdicts(true);
+        assert_eq!(
+            tally,
+            VerdictTally {
+                verdicts_for: 2,
+                verdicts_against: 1,
+                decided: true,
+                confidence_bps: 8_500,
+            }
+        );
+        assert_eq!(escalation, None);
+    }
+
+    #[test]
+    fn tally_verdicts_escalates_even_split() {
+        let escrow = voted_escrow(4, 3, &EVEN_SPLIT);
+        let (tally, escalation) = escrow.tally_verdicts(false);
+        assert!(!tally.decided);
+        assert_eq!((tally.verdicts_for, tally.verdicts_against), (2, 2));
+        assert_eq!(escalation, Some(EscalationReason::SplitVerdict));
+    }
+
+    #[test]
+    fn tally_verdicts_waits_while_threshold_reachable() {
+        // The fifth agent can still break the tie
+        let escrow = voted_escrow(5, 3, &EVEN_SPLIT);
+        let (tally, escalation) = escrow.tally_verdicts(false);
+        assert!(!tally.decided);
+        assert_eq!(escalation, None);
+        
+        // With the threshold at four, neither side can reach it any more
+        let escrow = voted_escrow(5, 4, &EVEN_SPLIT);
+        assert_eq!(escrow.tally_verdicts(false).1, Some(EscalationReason::SplitVerdict));
+    }
+
+    #[test]
+    fn tally_verdicts_escalates_low_confidence_pass() {
+        let escrow = voted_escrow(3, 2, &[(true, 9_000), (true, 6_000)]);
+        let (tally, escalation) = escrow.tally_verdicts(true);
+        assert!(tally.decided);
+        assert_eq!(tally.confidence_bps, 6_000);
+        assert_eq!(escalation, Some(EscalationReason::LowConfidence));
+        
+        // A low-confidence failure still refunds without a dispute
+        let escrow = voted_escrow(3, 2, &[(false, 9_000), (false, 6_000)]);
+        assert_eq!(escrow.tally_verdicts(false).1, None);
+    }
+}
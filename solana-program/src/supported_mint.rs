@@ -0,0 +1,142 @@
+use anchor_lang::prelude::*;
+
+use crate::{EscrowError, ProtocolConfig, MAX_FEE_BPS};
+
+/// Allowlist entry for a mint escrows may be created in. Native SOL is
+/// registered under `NATIVE_SOL_MINT`.
+#[account]
+#[derive(InitSpace)]
+pub struct SupportedMint {
+    pub mint: Pubkey,
+    pub enabled: bool,
+    pub min_amount: u64,
+    /// 0 = no cap
+    pub max_amount: u64,
+    /// Overrides the protocol fee rate for escrows in this mint
+    pub fee_bps: Option<u16>,
+    pub bump: u8,
+}
+
+impl SupportedMint {
+    /// Whether an escrow of `amount` may be created in this mint
+    pub fn check_amount(&self, amount: u64) -> Result<()> {
+        require!(self.enabled, EscrowError::UnsupportedMint);
+        require!(
+            amount >= self.min_amount && (self.max_amount == 0 || amount <= self.max_amount),
+            EscrowError::EscrowAmountOutOfRange
+        );
+        Ok(())
+    }
+
+    /// Fee rate new escrows in this mint snapshot: the mint's override if set,
+    /// otherwise the protocol rate
+    pub fn escrow_fee_bps(&self, config: &ProtocolConfig) -> u16 {
+        self.fee_bps.unwrap_or(config.fee_bps)
+    }
+
+    fn set_limits(
+        &mut self,
+        enabled: bool,
+        min_amount: u64,
+        max_amount: u64,
+        fee_bps: Option<u16>,
+    ) -> Result<()> {
+        require!(
+            max_amount == 0 || min_amount <= max_amount,
+            EscrowError::InvalidMintConfig
+        );
+        if let Some(fee_bps) = fee_bps {
+            require!(fee_bps <= MAX_FEE_BPS, EscrowError::InvalidFeeConfig);
+        }
+        
+        self.enabled = enabled;
+        self.min_amount = min_amount;
+        self.max_amount = max_amount;
+        self.fee_bps = fee_bps;
+        
+        emit!(SupportedMintUpdated {
+            mint: self.mint,
+            enabled,
+            min_amount,
+            max_amount,
+            fee_bps,
+        });
+        
+        Ok(())
+    }
+}
+
+/// Register a mint on the allowlist, enabled
+pub fn add_supported_mint(
+    ctx: Context<AddSupportedMint>,
+    mint: Pubkey,
+    min_amount: u64,
+    max_amount: u64,
+    fee_bps: Option<u16>,
+) -> Result<()> {
+    let supported_mint = &mut ctx.accounts.supported_mint;
+    supported_mint.mint = mint;
+    supported_mint.bump = ctx.bumps.supported_mint;
+    supported_mint.set_limits(true, min_amount, max_amount, fee_bps)
+}
+
+/// Change a listed mint's limits or fee override, or enable/disable it
+pub fn update_supported_mint(
+    ctx: Context<UpdateSupportedMint>,
+    enabled: bool,
+    min_amount: u64,
+    max_amount: u64,
+    fee_bps: Option<u16>,
+) -> Result<()> {
+    ctx.accounts
+        .supported_mint
+        .set_limits(enabled, min_amount, max_amount, fee_bps)
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct AddSupportedMint<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.authority == authority.key() @ EscrowError::UnauthorizedAdmin
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SupportedMint::INIT_SPACE,
+        seeds = [b"supported_mint", mint.as_ref()],
+        bump
+    )]
+    pub supported_mint: Account<'info, SupportedMint>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSupportedMint<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.authority == authority.key() @ EscrowError::UnauthorizedAdmin
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        seeds = [b"supported_mint", supported_mint.mint.as_ref()],
+        bump = supported_mint.bump
+    )]
+    pub supported_mint: Account<'info, SupportedMint>,
+}
+
+#[event]
+pub struct SupportedMintUpdated {
+    pub mint: Pubkey,
+    pub enabled: bool,
+    pub min_amount: u64,
+    pub max_amount: u64,
+    pub fee_bps: Option<u16>,
+}
@@ -1,5 +1,4 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer, Mint};
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct CrossChainMessage {
@@ -0,0 +1,217 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        default_account_state::DefaultAccountState, transfer_fee::TransferFeeAmount,
+        BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+    },
+    onchain::invoke_transfer_checked,
+    state::AccountState,
+};
+use anchor_spl::token_interface::{
+    self, CloseAccount, HarvestWithheldTokensToMint, Mint, TokenAccount, TokenInterface,
+};
+
+use crate::EscrowError;
+
+/// Refuse Token-2022 mints carrying extensions that break escrow guarantees.
+/// Legacy SPL Token mints have no extensions and always pass.
+pub fn validate_mint_extensions(mint: &InterfaceAccount<Mint>) -> Result<()> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+    
+    let data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    for extension in state.get_extension_types()? {
+        match extension {
+            // A permanent delegate can move tokens out of the vault behind our back
+            ExtensionType::PermanentDelegate => return err!(EscrowError::PermanentDelegateMint),
+            ExtensionType::NonTransferable => return err!(EscrowError::NonTransferableMint),
+            // Confidential balances are invisible to vault accounting
+            ExtensionType::ConfidentialTransferMint | ExtensionType::ConfidentialMintBurn => {
+                return err!(EscrowError::ConfidentialTransferMint)
+            },
+            // The pause authority could block settlement indefinitely
+            ExtensionType::Pausable => return err!(EscrowError::PausableMint),
+            ExtensionType::DefaultAccountState => {
+                let default_state = state.get_extension::<DefaultAccountState>()?;
+                require!(
+                    default_state.state != AccountState::Frozen as u8,
+                    EscrowError::FrozenByDefaultMint
+                );
+            },
+            _ => {},
+        }
+    }
+    
+    Ok(())
+}
+
+/// Accounts needed to move tokens in and out of an SPL escrow's vault.
+/// Native SOL escrows have none of these.
+pub(crate) struct VaultAccounts<'a, 'info> {
+    pub token_program: &'a Interface<'info, TokenInterface>,
+    pub escrow_vault: &'a InterfaceAccount<'info, TokenAccount>,
+    pub token_mint: &'a InterfaceAccount<'info, Mint>,
+    /// Extra accounts a Token-2022 transfer hook needs, passed as remaining accounts
+    pub hook_accounts: &'a [AccountInfo<'info>],
+}
+
+impl<'a, 'info> VaultAccounts<'a, 'info> {
+    /// Collect the vault accounts, or `None` for native SOL escrows
+    pub fn resolve(
+        is_native: bool,
+        token_program: Option<&'a Interface<'info, TokenInterface>>,
+        escrow_vault: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+        token_mint: Option<&'a InterfaceAccount<'info, Mint>>,
+        hook_accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Option<Self>> {
+        if is_native {
+            return Ok(None);
+        }
+        
+        match (token_program, escrow_vault, token_mint) {
+            (Some(token_program), Some(escrow_vault), Some(token_mint)) => Ok(Some(Self {
+                token_program,
+                escrow_vault,
+                token_mint,
+                hook_accounts,
+            })),
+            _ => err!(EscrowError::MissingSettlementAccount),
+        }
+    }
+
+    /// Move `amount` from `from` into the vault
+    pub fn deposit(&self, from: AccountInfo<'info>, authority: AccountInfo<'info>, amount: u64) -> Result<()> {
+        self.transfer_checked(from, self.escrow_vault.to_account_info(), authority, &[], amount)
+    }
+
+    /// Move `amount` out of the vault, signed by the escrow PDA
+    pub fn withdraw(
+        &self,
+        to: AccountInfo<'info>,
+        escrow: AccountInfo<'info>,
+        signer: &[&[&[u8]]],
+        amount: u64,
+    ) -> Result<()> {
+        self.transfer_checked(self.escrow_vault.to_account_info(), to, escrow, signer, amount)
+    }
+
+    /// `transfer_checked` that also forwards whatever accounts the mint's transfer hook needs
+    fn transfer_checked(
+        &self,
+        from: AccountInfo<'info>,
+        to: AccountInfo<'info>,
+        authority: AccountInfo<'info>,
+        signer: &[&[&[u8]]],
+        amount: u64,
+    ) -> Result<()> {
+        invoke_transfer_checked(
+            self.token_program.key,
+            from,
+            self.token_mint.to_account_info(),
+            to,
+            authority,
+            self.hook_accounts,
+            amount,
+            self.token_mint.decimals,
+            signer,
+        )
+        .map_err(Into::into)
+    }
+}
+
+/// Current token balance of the vault, read straight from account data so it
+/// reflects transfers made earlier in the same instruction
+pub(crate) fn vault_balance(vault: &AccountInfo) -> Result<u64> {
+    let data = vault.try_borrow_data()?;
+    let account = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+    Ok(account.base.amount)
+}
+
+/// Close an emptied vault, signed by the escrow PDA, sending its rent to
+/// `destination`. Transfer fees withheld on the vault are harvested to the
+/// mint first, since Token-2022 refuses to close an account holding them.
+pub(crate) fn close_vault<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    escrow_vault: &InterfaceAccount<'info, TokenAccount>,
+    token_mint: &InterfaceAccount<'info, Mint>,
+    destination: AccountInfo<'info>,
+    escrow: AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    let vault_info = escrow_vault.to_account_info();
+    require!(vault_balance(&vault_info)? == 0, EscrowError::VaultNotEmpty);
+    
+    let withheld_amount = {
+        let data = vault_info.try_borrow_data()?;
+        let account = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+        account
+            .get_extension::<TransferFeeAmount>()
+            .map_or(0, |fee| u64::from(fee.withheld_amount))
+    };
+    if withheld_amount > 0 {
+        token_interface::harvest_withheld_tokens_to_mint(
+            CpiContext::new(
+                token_program.to_account_info(),
+                HarvestWithheldTokensToMint {
+                    token_program_id: token_program.to_account_info(),
+                    mint: token_mint.to_account_info(),
+                },
+            ),
+            vec![vault_info.clone()],
+        )?;
+    }
+    
+    token_interface::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: vault_info,
+            destination,
+            authority: escrow,
+        },
+        signer,
+    ))
+}
+
+/// Transfer `amount` out of the escrow vault, signed by the escrow PDA.
+/// Native SOL escrows pay out lamports held on the escrow account itself.
+pub(crate) fn transfer_from_vault<'info>(
+    vault: Option<&VaultAccounts<'_, 'info>>,
+    to: AccountInfo<'info>,
+    escrow: AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    
+    match vault {
+        Some(vault) => vault.withdraw(to, escrow, signer, amount),
+        None => {
+            // The escrow PDA is owned by this program, so lamports move without a CPI
+            escrow.sub_lamports(amount)?;
+            to.add_lamports(amount)?;
+            Ok(())
+        },
+    }
+}
+
+/// Pick the account a payout goes to: the wallet for native SOL escrows,
+/// the token account otherwise
+pub(crate) fn settlement_destination<'info>(
+    is_native: bool,
+    token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+    wallet: Option<&SystemAccount<'info>>,
+) -> Result<AccountInfo<'info>> {
+    let destination = if is_native {
+        wallet.map(|wallet| wallet.to_account_info())
+    } else {
+        token_account.map(|account| account.to_account_info())
+    };
+    
+    destination.ok_or_else(|| error!(EscrowError::MissingSettlementAccount))
+}
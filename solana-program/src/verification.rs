@@ -0,0 +1,163 @@
+use anchor_lang::prelude::*;
+
+use crate::{EscrowError, BPS_DENOMINATOR, ID};
+
+/// Layout version of the payload AI agents sign. Bumped whenever a field is added.
+pub const VERIFICATION_PAYLOAD_VERSION: u8 = 3;
+
+/// Verdict an AI agent signs for a milestone
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct VerificationPayload {
+    pub version: u8,
+    pub result: bool,
+    /// Agent's confidence in `result`, in basis points
+    pub confidence_bps: u16,
+    pub evidence_hash: [u8; 32],
+    /// Identifies the model and version that produced the verdict
+    pub model_hash: [u8; 32],
+    /// Hash of the full off-chain verification report
+    pub report_hash: [u8; 32],
+    /// Must exceed the last nonce this agent used on the escrow
+    pub nonce: u64,
+    pub timestamp: i64,
+}
+
+impl VerificationPayload {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.version == VERIFICATION_PAYLOAD_VERSION,
+            EscrowError::UnsupportedPayloadVersion
+        );
+        require!(
+            self.confidence_bps as u64 <= BPS_DENOMINATOR,
+            EscrowError::InvalidConfidence
+        );
+        Ok(())
+    }
+
+    /// Bytes the agent signs over. The program ID and domain separator bind the
+    /// verdict to this deployment and cluster, the request ID to one verification round.
+    /// Format: version (1 byte) + program_id (32 bytes) + domain_separator (32 bytes)
+    ///         + escrow_id (32 bytes) + milestone_index (1 byte)
+    ///         + chainlink_request_id (32 bytes) + result (1 byte)
+    ///         + confidence_bps (2 bytes) + evidence_hash (32 bytes) + model_hash (32 bytes)
+    ///         + report_hash (32 bytes) + nonce (8 bytes) + timestamp (8 bytes)
+    pub fn message(
+        &self,
+        domain_separator: &[u8; 32],
+        escrow_id: &[u8; 32],
+        milestone_index: u8,
+        chainlink_request_id: &[u8; 32],
+    ) -> Vec<u8> {
+        let mut message = Vec::with_capacity(245);
+        message.push(self.version);
+        message.extend_from_slice(ID.as_ref());
+        message.extend_from_slice(domain_separator);
+        message.extend_from_slice(escrow_id);
+        message.push(milestone_index);
+        message.extend_from_slice(chainlink_request_id);
+        message.push(if self.result { 1u8 } else { 0u8 });
+        message.extend_from_slice(&self.confidence_bps.to_le_bytes());
+        message.extend_from_slice(&self.evidence_hash);
+        message.extend_from_slice(&self.model_hash);
+        message.extend_from_slice(&self.report_hash);
+        message.extend_from_slice(&self.nonce.to_le_bytes());
+        message.extend_from_slice(&self.timestamp.to_le_bytes());
+        message
+    }
+}
+
+/// Where a verification round stands after the latest verdict
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct VerdictTally {
+    pub verdicts_for: u8,
+    pub verdicts_against: u8,
+    /// The latest verdict took its side to the escrow's threshold
+    pub decided: bool,
+    /// Lowest confidence among the agents agreeing with the latest verdict
+    pub confidence_bps: u16,
+}
+
+/// Why a verification round went to an arbitrator panel instead of settling
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EscalationReason {
+    /// Neither verdict can reach the escrow's threshold any more
+    SplitVerdict,
+    /// The agents passed the milestone below the escrow's minimum confidence
+    LowConfidence,
+}
+
+#[event]
+pub struct DomainSeparatorUpdated {
+    pub domain_separator: [u8; 32],
+}
+
+#[event]
+pub struct VerificationEscalated {
+    pub escrow_id: [u8; 32],
+    pub milestone_index: u8,
+    pub reason: EscalationReason,
+    pub verdicts_for: u8,
+    pub verdicts_against: u8,
+    pub confidence_bps: u16,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload() -> VerificationPayload {
+        VerificationPayload {
+            version: VERIFICATION_PAYLOAD_VERSION,
+            result: true,
+            confidence_bps: 0x1234,
+            evidence_hash: [0xe1; 32],
+            model_hash: [0xe2; 32],
+            report_hash: [0xe3; 32],
+            nonce: 0x0102_0304_0506_0708,
+            timestamp: -2,
+        }
+    }
+
+    #[test]
+    fn validate_checks_version_and_confidence() {
+        assert!(payload().validate().is_ok());
+        
+        let mut payload = payload();
+        payload.confidence_bps = 10_000;
+        assert!(payload.validate().is_ok());
+        payload.confidence_bps = 10_001;
+        assert!(payload.validate().is_err());
+        
+        payload.confidence_bps = 0;
+        payload.version = VERIFICATION_PAYLOAD_VERSION - 1;
+        assert!(payload.validate().is_err());
+    }
+
+    #[test]
+    fn message_layout() {
+        let message = payload().message(&[0xd0; 32], &[0xa0; 32], 7, &[0xc0; 32]);
+        
+        assert_eq!(message.len(), 245);
+        assert_eq!(message[0], VERIFICATION_PAYLOAD_VERSION);
+        assert_eq!(&message[1..33], ID.as_ref());
+        assert_eq!(&message[33..65], &[0xd0; 32]);
+        assert_eq!(&message[65..97], &[0xa0; 32]);
+        assert_eq!(message[97], 7);
+        assert_eq!(&message[98..130], &[0xc0; 32]);
+        assert_eq!(message[130], 1);
+        assert_eq!(&message[131..133], &[0x34, 0x12]);
+        assert_eq!(&message[133..165], &[0xe1; 32]);
+        assert_eq!(&message[165..197], &[0xe2; 32]);
+        assert_eq!(&message[197..229], &[0xe3; 32]);
+        assert_eq!(&message[229..237], &[8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(&message[237..245], &[0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn message_encodes_failing_result() {
+        let mut payload = payload();
+        payload.result = false;
+        assert_eq!(payload.message(&[0; 32], &[0; 32], 0, &[0; 32])[130], 0);
+    }
+}
@@ -1,24 +1,42 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { AetherlockEscrow } from "../target/types/aetherlock_escrow";
-import { 
-  PublicKey, 
-  Keypair, 
-  SystemProgram, 
-  SYSVAR_CLOCK_PUBKEY,
-  Transaction,
-  sendAndConfirmTransaction
+import {
+  PublicKey,
+  Keypair
 } from "@solana/web3.js";
-import { 
-  TOKEN_PROGRAM_ID, 
-  createMint, 
-  createAccount, 
-  mintTo, 
-  getAccount 
+import {
+  createMint,
+  createAccount,
+  mintTo,
+  getAccount
 } from "@solana/spl-token";
 import { expect } from "chai";
 import * as crypto from "crypto";
-import * as ed25519 from "@noble/ed25519";
+import {
+  EscrowParties,
+  acceptEscrow,
+  castDisputeVotes,
+  configPda,
+  createFundedEscrow,
+  depositFunds,
+  disputePda,
+  escrowPda,
+  expectedFee,
+  finalizeDispute,
+  initializeEscrow,
+  now,
+  raiseDispute,
+  refundBuyer,
+  releaseFunds,
+  requestVerification,
+  setupProtocol,
+  submitVerification,
+  supportMint,
+  vaultPda,
+  verificationPayload,
+  verifyMilestone
+} from "./helpers";
 
 describe("AetherLock Escrow Protocol", () => {
   // Configure the client to use the local cluster
@@ -26,60 +44,61 @@ describe("AetherLock Escrow Protocol", () => {
   anchor.setProvider(provider);
 
   const program = anchor.workspace.AetherlockEscrow as Program<AetherlockEscrow>;
-  
+
   // Test accounts
-  let authority: Keypair;
   let buyer: Keypair;
   let seller: Keypair;
   let aiAgent: Keypair;
   let admin1: Keypair;
   let admin2: Keypair;
   let unauthorizedUser: Keypair;
-  
+  let mintAuthority: Keypair;
+
   // Token accounts
   let tokenMint: PublicKey;
   let buyerTokenAccount: PublicKey;
   let sellerTokenAccount: PublicKey;
   let protocolTreasury: PublicKey;
-  
+  let parties: EscrowParties;
+
   // Protocol config
   let configPDA: PublicKey;
-  let configBump: number;
-  
+
   // Test constants
   const ESCROW_AMOUNT = 1000000; // 1 token (6 decimals)
-  const PROTOCOL_FEE = 20000; // 2% of 1000000
-  const SELLER_AMOUNT = 980000; // Amount after fee deduction
-  
+  const PROTOCOL_FEE = expectedFee(ESCROW_AMOUNT); // 10% of 1000000
+  const SELLER_AMOUNT = ESCROW_AMOUNT - PROTOCOL_FEE; // Amount after fee deduction
+  const MAX_ESCROW_AMOUNT = 100000000; // Per-mint cap on escrow size
+
   before(async () => {
     // Initialize test accounts
-    authority = Keypair.generate();
+    mintAuthority = Keypair.generate();
     buyer = Keypair.generate();
     seller = Keypair.generate();
     aiAgent = Keypair.generate();
     admin1 = Keypair.generate();
     admin2 = Keypair.generate();
     unauthorizedUser = Keypair.generate();
-    
+
     // Airdrop SOL to test accounts
     await Promise.all([
-      provider.connection.requestAirdrop(authority.publicKey, 2 * anchor.web3.LAMPORTS_PER_SOL),
+      provider.connection.requestAirdrop(mintAuthority.publicKey, 2 * anchor.web3.LAMPORTS_PER_SOL),
       provider.connection.requestAirdrop(buyer.publicKey, 2 * anchor.web3.LAMPORTS_PER_SOL),
       provider.connection.requestAirdrop(seller.publicKey, 2 * anchor.web3.LAMPORTS_PER_SOL),
       provider.connection.requestAirdrop(admin1.publicKey, 1 * anchor.web3.LAMPORTS_PER_SOL),
       provider.connection.requestAirdrop(admin2.publicKey, 1 * anchor.web3.LAMPORTS_PER_SOL),
       provider.connection.requestAirdrop(unauthorizedUser.publicKey, 1 * anchor.web3.LAMPORTS_PER_SOL),
     ]);
-    
+
     // Create token mint
     tokenMint = await createMint(
       provider.connection,
-      authority,
-      authority.publicKey,
+      mintAuthority,
+      mintAuthority.publicKey,
       null,
       6
     );
-    
+
     // Create token accounts
     buyerTokenAccount = await createAccount(
       provider.connection,
@@ -87,130 +106,118 @@ describe("AetherLock Escrow Protocol", () => {
       tokenMint,
       buyer.publicKey
     );
-    
+
     sellerTokenAccount = await createAccount(
       provider.connection,
       seller,
       tokenMint,
       seller.publicKey
     );
-    
-    protocolTreasury = await createAccount(
-      provider.connection,
-      authority,
-      tokenMint,
-      authority.publicKey
-    );
-    
+
     // Mint tokens to buyer
     await mintTo(
       provider.connection,
-      authority,
+      mintAuthority,
       tokenMint,
       buyerTokenAccount,
-      authority,
-      10000000 // 10 tokens
-    );
-    
-    // Find config PDA
-    [configPDA, configBump] = PublicKey.findProgramAddressSync(
-      [Buffer.from("config")],
-      program.programId
+      mintAuthority,
+      20000000 // 20 tokens
     );
-    
-    // Initialize protocol config
-    await program.methods
-      .initializeConfig([admin1.publicKey, admin2.publicKey])
-      .accounts({
-        authority: authority.publicKey,
-        config: configPDA,
-        systemProgram: SystemProgram.programId,
-      })
-      .signers([authority])
-      .rpc();
+
+    // Protocol config is shared by all suites and owned by the provider wallet
+    configPDA = await setupProtocol(program);
+    protocolTreasury = await supportMint(program, tokenMint, 1, MAX_ESCROW_AMOUNT);
+
+    parties = {
+      buyer,
+      seller,
+      aiAgent,
+      tokenMint,
+      buyerTokenAccount,
+      sellerTokenAccount,
+    };
+
+    for (const admin of [admin1, admin2]) {
+      await program.methods
+        .addAdmin(admin.publicKey)
+        .accountsPartial({
+          authority: provider.wallet.publicKey,
+          config: configPDA,
+        })
+        .rpc();
+    }
   });
 
   describe("Protocol Configuration", () => {
     it("should initialize protocol config with admin addresses", async () => {
       const config = await program.account.protocolConfig.fetch(configPDA);
-      expect(config.authority.toString()).to.equal(authority.publicKey.toString());
-      expect(config.adminPubkeys).to.have.lengthOf(2);
-      expect(config.adminPubkeys[0].toString()).to.equal(admin1.publicKey.toString());
-      expect(config.adminPubkeys[1].toString()).to.equal(admin2.publicKey.toString());
+      const admins = config.adminPubkeys.map(k => k.toString());
+      expect(config.authority.toString()).to.equal(provider.wallet.publicKey.toString());
+      expect(admins).to.include(admin1.publicKey.toString());
+      expect(admins).to.include(admin2.publicKey.toString());
     });
 
     it("should add new admin to authorized list", async () => {
       const newAdmin = Keypair.generate();
-      
+
       await program.methods
         .addAdmin(newAdmin.publicKey)
-        .accounts({
-          authority: authority.publicKey,
+        .accountsPartial({
+          authority: provider.wallet.publicKey,
           config: configPDA,
         })
-        .signers([authority])
         .rpc();
-      
+
       const config = await program.account.protocolConfig.fetch(configPDA);
-      expect(config.adminPubkeys).to.have.lengthOf(3);
-      expect(config.adminPubkeys[2].toString()).to.equal(newAdmin.publicKey.toString());
+      expect(config.adminPubkeys.map(k => k.toString())).to.include(newAdmin.publicKey.toString());
     });
 
     it("should remove admin from authorized list", async () => {
       await program.methods
         .removeAdmin(admin2.publicKey)
-        .accounts({
-          authority: authority.publicKey,
+        .accountsPartial({
+          authority: provider.wallet.publicKey,
           config: configPDA,
         })
-        .signers([authority])
         .rpc();
-      
+
       const config = await program.account.protocolConfig.fetch(configPDA);
-      expect(config.adminPubkeys).to.have.lengthOf(2);
       expect(config.adminPubkeys.map(k => k.toString())).to.not.include(admin2.publicKey.toString());
     });
+
+    it("should reject config changes from a non-authority", async () => {
+      try {
+        await program.methods
+          .addAdmin(unauthorizedUser.publicKey)
+          .accountsPartial({
+            authority: unauthorizedUser.publicKey,
+            config: configPDA,
+          })
+          .signers([unauthorizedUser])
+          .rpc();
+
+        expect.fail("Should have rejected unauthorized authority");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+    });
   });
 
   describe("Escrow State Transitions", () => {
     let escrowId: Buffer;
     let escrowPDA: PublicKey;
-    let escrowBump: number;
     let vaultPDA: PublicKey;
-    let vaultBump: number;
-    
+
     beforeEach(async () => {
       // Generate unique escrow ID for each test
       escrowId = crypto.randomBytes(32);
-      
-      [escrowPDA, escrowBump] = PublicKey.findProgramAddressSync(
-        [Buffer.from("escrow"), escrowId],
-        program.programId
-      );
+      escrowPDA = escrowPda(program, escrowId);
+      vaultPDA = vaultPda(program, escrowPDA);
     });
 
     it("should initialize escrow in Created state", async () => {
-      const expiry = Math.floor(Date.now() / 1000) + 3600; // 1 hour from now
-      const metadataHash = crypto.randomBytes(32);
-      
-      await program.methods
-        .initializeEscrow(
-          Array.from(escrowId),
-          seller.publicKey,
-          new anchor.BN(ESCROW_AMOUNT),
-          new anchor.BN(expiry),
-          Array.from(metadataHash),
-          aiAgent.publicKey
-        )
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          tokenMint: tokenMint,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
-      
+      await initializeEscrow(program, parties, escrowId, new anchor.BN(ESCROW_AMOUNT));
+
       const escrow = await program.account.escrowAccount.fetch(escrowPDA);
       expect(escrow.status).to.deep.equal({ created: {} });
       expect(escrow.buyer.toString()).to.equal(buyer.publicKey.toString());
@@ -220,277 +227,74 @@ describe("AetherLock Escrow Protocol", () => {
       expect(escrow.aiAgentPubkey.toString()).to.equal(aiAgent.publicKey.toString());
     });
 
-    it("should transition from Created to Funded on deposit", async () => {
-      const expiry = Math.floor(Date.now() / 1000) + 3600;
-      const metadataHash = crypto.randomBytes(32);
-      
-      // Initialize escrow
-      await program.methods
-        .initializeEscrow(
-          Array.from(escrowId),
-          seller.publicKey,
-          new anchor.BN(ESCROW_AMOUNT),
-          new anchor.BN(expiry),
-          Array.from(metadataHash),
-          aiAgent.publicKey
-        )
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          tokenMint: tokenMint,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
-      
-      // Find vault PDA
-      [vaultPDA, vaultBump] = PublicKey.findProgramAddressSync(
-        [Buffer.from("vault"), escrowPDA.toBuffer()],
-        program.programId
-      );
-      
-      // Deposit funds
-      await program.methods
-        .depositFunds()
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          escrowVault: vaultPDA,
-          buyerTokenAccount: buyerTokenAccount,
-          tokenProgram: TOKEN_PROGRAM_ID,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
-      
+    it("should transition from Created to Accepted when the seller accepts", async () => {
+      await initializeEscrow(program, parties, escrowId, new anchor.BN(ESCROW_AMOUNT));
+      await acceptEscrow(program, parties, escrowId);
+
+      const escrow = await program.account.escrowAccount.fetch(escrowPDA);
+      expect(escrow.status).to.deep.equal({ accepted: {} });
+      expect(escrow.acceptedAt).to.not.be.null;
+    });
+
+    it("should reject funding before the seller accepts", async () => {
+      await initializeEscrow(program, parties, escrowId, new anchor.BN(ESCROW_AMOUNT));
+
+      try {
+        await depositFunds(program, parties, escrowId);
+
+        expect.fail("Should have rejected deposit into an unaccepted escrow");
+      } catch (error) {
+        expect(error.message).to.include("InvalidEscrowState");
+      }
+    });
+
+    it("should transition from Accepted to Funded on deposit", async () => {
+      await createFundedEscrow(program, parties, escrowId, new anchor.BN(ESCROW_AMOUNT));
+
       const escrow = await program.account.escrowAccount.fetch(escrowPDA);
       expect(escrow.status).to.deep.equal({ funded: {} });
-      
+
       // Verify tokens were transferred to vault
       const vaultAccount = await getAccount(provider.connection, vaultPDA);
       expect(Number(vaultAccount.amount)).to.equal(ESCROW_AMOUNT);
     });
 
     it("should transition from Funded to Verified on AI verification", async () => {
-      // Setup escrow and deposit funds
-      const expiry = Math.floor(Date.now() / 1000) + 3600;
-      const metadataHash = crypto.randomBytes(32);
-      
-      await program.methods
-        .initializeEscrow(
-          Array.from(escrowId),
-          seller.publicKey,
-          new anchor.BN(ESCROW_AMOUNT),
-          new anchor.BN(expiry),
-          Array.from(metadataHash),
-          aiAgent.publicKey
-        )
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          tokenMint: tokenMint,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
-      
-      [vaultPDA, vaultBump] = PublicKey.findProgramAddressSync(
-        [Buffer.from("vault"), escrowPDA.toBuffer()],
-        program.programId
-      );
-      
-      await program.methods
-        .depositFunds()
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          escrowVault: vaultPDA,
-          buyerTokenAccount: buyerTokenAccount,
-          tokenProgram: TOKEN_PROGRAM_ID,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
-      
-      // Create AI verification signature
-      const result = true;
-      const evidenceHash = crypto.randomBytes(32);
-      const timestamp = Math.floor(Date.now() / 1000);
-      
-      const message = Buffer.concat([
-        Buffer.from(escrowId),
-        Buffer.from([result ? 1 : 0]),
-        Buffer.from(evidenceHash),
-        Buffer.from(new Uint8Array(new BigInt64Array([BigInt(timestamp)]).buffer))
-      ]);
-      
-      const signature = await ed25519.sign(message, aiAgent.secretKey.slice(0, 32));
-      
-      // Submit verification
-      await program.methods
-        .submitVerification(
-          result,
-          Array.from(evidenceHash),
-          new anchor.BN(timestamp),
-          Array.from(signature)
-        )
-        .accounts({
-          escrow: escrowPDA,
-          aiAgent: aiAgent.publicKey,
-        })
-        .rpc();
-      
+      await createFundedEscrow(program, parties, escrowId, new anchor.BN(ESCROW_AMOUNT));
+
+      await verifyMilestone(program, parties, escrowId, true);
+
       const escrow = await program.account.escrowAccount.fetch(escrowPDA);
       expect(escrow.status).to.deep.equal({ verified: {} });
       expect(escrow.verificationResult).to.equal(true);
+      expect(escrow.agentNonces[0].toNumber()).to.equal(1);
     });
 
     it("should transition from Verified to Released on fund release", async () => {
-      // Setup complete escrow flow
-      const expiry = Math.floor(Date.now() / 1000) + 3600;
-      const metadataHash = crypto.randomBytes(32);
-      
-      await program.methods
-        .initializeEscrow(
-          Array.from(escrowId),
-          seller.publicKey,
-          new anchor.BN(ESCROW_AMOUNT),
-          new anchor.BN(expiry),
-          Array.from(metadataHash),
-          aiAgent.publicKey
-        )
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          tokenMint: tokenMint,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
-      
-      [vaultPDA, vaultBump] = PublicKey.findProgramAddressSync(
-        [Buffer.from("vault"), escrowPDA.toBuffer()],
-        program.programId
-      );
-      
-      await program.methods
-        .depositFunds()
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          escrowVault: vaultPDA,
-          buyerTokenAccount: buyerTokenAccount,
-          tokenProgram: TOKEN_PROGRAM_ID,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
-      
-      // AI verification
-      const result = true;
-      const evidenceHash = crypto.randomBytes(32);
-      const timestamp = Math.floor(Date.now() / 1000);
-      
-      const message = Buffer.concat([
-        Buffer.from(escrowId),
-        Buffer.from([result ? 1 : 0]),
-        Buffer.from(evidenceHash),
-        Buffer.from(new Uint8Array(new BigInt64Array([BigInt(timestamp)]).buffer))
-      ]);
-      
-      const signature = await ed25519.sign(message, aiAgent.secretKey.slice(0, 32));
-      
-      await program.methods
-        .submitVerification(
-          result,
-          Array.from(evidenceHash),
-          new anchor.BN(timestamp),
-          Array.from(signature)
-        )
-        .accounts({
-          escrow: escrowPDA,
-          aiAgent: aiAgent.publicKey,
-        })
-        .rpc();
-      
-      // Release funds
-      await program.methods
-        .releaseFunds()
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          escrowVault: vaultPDA,
-          sellerTokenAccount: sellerTokenAccount,
-          protocolTreasury: protocolTreasury,
-          tokenProgram: TOKEN_PROGRAM_ID,
-        })
-        .signers([buyer])
-        .rpc();
-      
+      await createFundedEscrow(program, parties, escrowId, new anchor.BN(ESCROW_AMOUNT));
+      await verifyMilestone(program, parties, escrowId, true);
+
+      const initialSellerBalance = Number((await getAccount(provider.connection, sellerTokenAccount)).amount);
+      const initialTreasuryBalance = Number((await getAccount(provider.connection, protocolTreasury)).amount);
+
+      await releaseFunds(program, parties, escrowId);
+
       const escrow = await program.account.escrowAccount.fetch(escrowPDA);
       expect(escrow.status).to.deep.equal({ released: {} });
-      
+
       // Verify fund distribution
       const sellerAccount = await getAccount(provider.connection, sellerTokenAccount);
       const treasuryAccount = await getAccount(provider.connection, protocolTreasury);
-      
-      expect(Number(sellerAccount.amount)).to.equal(SELLER_AMOUNT);
-      expect(Number(treasuryAccount.amount)).to.equal(PROTOCOL_FEE);
+
+      expect(Number(sellerAccount.amount)).to.equal(initialSellerBalance + SELLER_AMOUNT);
+      expect(Number(treasuryAccount.amount)).to.equal(initialTreasuryBalance + PROTOCOL_FEE);
     });
 
     it("should transition to Disputed state when dispute is raised", async () => {
-      // Setup funded escrow
-      const expiry = Math.floor(Date.now() / 1000) + 3600;
-      const metadataHash = crypto.randomBytes(32);
-      
-      await program.methods
-        .initializeEscrow(
-          Array.from(escrowId),
-          seller.publicKey,
-          new anchor.BN(ESCROW_AMOUNT),
-          new anchor.BN(expiry),
-          Array.from(metadataHash),
-          aiAgent.publicKey
-        )
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          tokenMint: tokenMint,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
-      
-      [vaultPDA, vaultBump] = PublicKey.findProgramAddressSync(
-        [Buffer.from("vault"), escrowPDA.toBuffer()],
-        program.programId
-      );
-      
-      await program.methods
-        .depositFunds()
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          escrowVault: vaultPDA,
-          buyerTokenAccount: buyerTokenAccount,
-          tokenProgram: TOKEN_PROGRAM_ID,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
-      
-      // Raise dispute
-      const reasonHash = crypto.randomBytes(32);
-      
-      await program.methods
-        .raiseDispute(Array.from(reasonHash))
-        .accounts({
-          participant: buyer.publicKey,
-          escrow: escrowPDA,
-        })
-        .signers([buyer])
-        .rpc();
-      
+      await createFundedEscrow(program, parties, escrowId, new anchor.BN(ESCROW_AMOUNT));
+
+      await raiseDispute(program, parties, escrowId, buyer);
+
       const escrow = await program.account.escrowAccount.fetch(escrowPDA);
       expect(escrow.status).to.deep.equal({ disputed: {} });
       expect(escrow.disputeRaised).to.be.true;
@@ -498,93 +302,20 @@ describe("AetherLock Escrow Protocol", () => {
     });
 
     it("should transition to Refunded state on buyer refund", async () => {
-      // Setup escrow with failed verification
-      const expiry = Math.floor(Date.now() / 1000) + 3600;
-      const metadataHash = crypto.randomBytes(32);
-      
-      await program.methods
-        .initializeEscrow(
-          Array.from(escrowId),
-          seller.publicKey,
-          new anchor.BN(ESCROW_AMOUNT),
-          new anchor.BN(expiry),
-          Array.from(metadataHash),
-          aiAgent.publicKey
-        )
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          tokenMint: tokenMint,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
-      
-      [vaultPDA, vaultBump] = PublicKey.findProgramAddressSync(
-        [Buffer.from("vault"), escrowPDA.toBuffer()],
-        program.programId
-      );
-      
-      await program.methods
-        .depositFunds()
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          escrowVault: vaultPDA,
-          buyerTokenAccount: buyerTokenAccount,
-          tokenProgram: TOKEN_PROGRAM_ID,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
-      
+      await createFundedEscrow(program, parties, escrowId, new anchor.BN(ESCROW_AMOUNT));
+
       // AI verification with false result
-      const result = false;
-      const evidenceHash = crypto.randomBytes(32);
-      const timestamp = Math.floor(Date.now() / 1000);
-      
-      const message = Buffer.concat([
-        Buffer.from(escrowId),
-        Buffer.from([result ? 1 : 0]),
-        Buffer.from(evidenceHash),
-        Buffer.from(new Uint8Array(new BigInt64Array([BigInt(timestamp)]).buffer))
-      ]);
-      
-      const signature = await ed25519.sign(message, aiAgent.secretKey.slice(0, 32));
-      
-      await program.methods
-        .submitVerification(
-          result,
-          Array.from(evidenceHash),
-          new anchor.BN(timestamp),
-          Array.from(signature)
-        )
-        .accounts({
-          escrow: escrowPDA,
-          aiAgent: aiAgent.publicKey,
-        })
-        .rpc();
-      
+      await verifyMilestone(program, parties, escrowId, false);
+
       // Get initial buyer balance
       const initialBuyerAccount = await getAccount(provider.connection, buyerTokenAccount);
       const initialBalance = Number(initialBuyerAccount.amount);
-      
-      // Refund buyer
-      await program.methods
-        .refundBuyer()
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          escrowVault: vaultPDA,
-          buyerTokenAccount: buyerTokenAccount,
-          tokenProgram: TOKEN_PROGRAM_ID,
-        })
-        .signers([buyer])
-        .rpc();
-      
+
+      await refundBuyer(program, parties, escrowId);
+
       const escrow = await program.account.escrowAccount.fetch(escrowPDA);
       expect(escrow.status).to.deep.equal({ refunded: {} });
-      
+
       // Verify full refund
       const finalBuyerAccount = await getAccount(provider.connection, buyerTokenAccount);
       expect(Number(finalBuyerAccount.amount)).to.equal(initialBalance + ESCROW_AMOUNT);
@@ -594,118 +325,29 @@ describe("AetherLock Escrow Protocol", () => {
   describe("AI Agent Signature Verification", () => {
     let escrowId: Buffer;
     let escrowPDA: PublicKey;
-    let vaultPDA: PublicKey;
-    
+    let requestId: Buffer;
+
     beforeEach(async () => {
       escrowId = crypto.randomBytes(32);
-      
-      [escrowPDA] = PublicKey.findProgramAddressSync(
-        [Buffer.from("escrow"), escrowId],
-        program.programId
-      );
-      
-      [vaultPDA] = PublicKey.findProgramAddressSync(
-        [Buffer.from("vault"), escrowPDA.toBuffer()],
-        program.programId
-      );
-      
-      // Setup funded escrow
-      const expiry = Math.floor(Date.now() / 1000) + 3600;
-      const metadataHash = crypto.randomBytes(32);
-      
-      await program.methods
-        .initializeEscrow(
-          Array.from(escrowId),
-          seller.publicKey,
-          new anchor.BN(ESCROW_AMOUNT),
-          new anchor.BN(expiry),
-          Array.from(metadataHash),
-          aiAgent.publicKey
-        )
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          tokenMint: tokenMint,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
-      
-      await program.methods
-        .depositFunds()
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          escrowVault: vaultPDA,
-          buyerTokenAccount: buyerTokenAccount,
-          tokenProgram: TOKEN_PROGRAM_ID,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
+      escrowPDA = await createFundedEscrow(program, parties, escrowId, new anchor.BN(ESCROW_AMOUNT));
+      requestId = await requestVerification(program, parties, escrowId);
     });
 
     it("should accept valid AI agent signature", async () => {
-      const result = true;
-      const evidenceHash = crypto.randomBytes(32);
-      const timestamp = Math.floor(Date.now() / 1000);
-      
-      const message = Buffer.concat([
-        Buffer.from(escrowId),
-        Buffer.from([result ? 1 : 0]),
-        Buffer.from(evidenceHash),
-        Buffer.from(new Uint8Array(new BigInt64Array([BigInt(timestamp)]).buffer))
-      ]);
-      
-      const signature = await ed25519.sign(message, aiAgent.secretKey.slice(0, 32));
-      
-      await program.methods
-        .submitVerification(
-          result,
-          Array.from(evidenceHash),
-          new anchor.BN(timestamp),
-          Array.from(signature)
-        )
-        .accounts({
-          escrow: escrowPDA,
-          aiAgent: aiAgent.publicKey,
-        })
-        .rpc();
-      
+      await submitVerification(program, escrowId, aiAgent, requestId, verificationPayload(true, 1));
+
       const escrow = await program.account.escrowAccount.fetch(escrowPDA);
-      expect(escrow.verificationResult).to.equal(true);
       expect(escrow.status).to.deep.equal({ verified: {} });
+      expect(escrow.verificationResult).to.equal(true);
+      expect(escrow.confidenceBps).to.equal(9000);
     });
 
     it("should reject signature from unauthorized AI agent", async () => {
       const unauthorizedAgent = Keypair.generate();
-      const result = true;
-      const evidenceHash = crypto.randomBytes(32);
-      const timestamp = Math.floor(Date.now() / 1000);
-      
-      const message = Buffer.concat([
-        Buffer.from(escrowId),
-        Buffer.from([result ? 1 : 0]),
-        Buffer.from(evidenceHash),
-        Buffer.from(new Uint8Array(new BigInt64Array([BigInt(timestamp)]).buffer))
-      ]);
-      
-      const signature = await ed25519.sign(message, unauthorizedAgent.secretKey.slice(0, 32));
-      
+
       try {
-        await program.methods
-          .submitVerification(
-            result,
-            Array.from(evidenceHash),
-            new anchor.BN(timestamp),
-            Array.from(signature)
-          )
-          .accounts({
-            escrow: escrowPDA,
-            aiAgent: unauthorizedAgent.publicKey,
-          })
-          .rpc();
-        
+        await submitVerification(program, escrowId, unauthorizedAgent, requestId, verificationPayload(true, 1));
+
         expect.fail("Should have rejected unauthorized AI agent");
       } catch (error) {
         expect(error.message).to.include("UnauthorizedAIAgent");
@@ -713,27 +355,19 @@ describe("AetherLock Escrow Protocol", () => {
     });
 
     it("should reject invalid signature", async () => {
-      const result = true;
-      const evidenceHash = crypto.randomBytes(32);
-      const timestamp = Math.floor(Date.now() / 1000);
-      
       // Create invalid signature (random bytes)
       const invalidSignature = crypto.randomBytes(64);
-      
+
       try {
-        await program.methods
-          .submitVerification(
-            result,
-            Array.from(evidenceHash),
-            new anchor.BN(timestamp),
-            Array.from(invalidSignature)
-          )
-          .accounts({
-            escrow: escrowPDA,
-            aiAgent: aiAgent.publicKey,
-          })
-          .rpc();
-        
+        await submitVerification(
+          program,
+          escrowId,
+          aiAgent,
+          requestId,
+          verificationPayload(true, 1),
+          { signature: invalidSignature }
+        );
+
         expect.fail("Should have rejected invalid signature");
       } catch (error) {
         expect(error.message).to.include("InvalidSignature");
@@ -741,74 +375,73 @@ describe("AetherLock Escrow Protocol", () => {
     });
 
     it("should reject signature with tampered message", async () => {
-      const result = true;
-      const evidenceHash = crypto.randomBytes(32);
-      const timestamp = Math.floor(Date.now() / 1000);
-      
-      // Create signature for original message
-      const originalMessage = Buffer.concat([
-        Buffer.from(escrowId),
-        Buffer.from([result ? 1 : 0]),
-        Buffer.from(evidenceHash),
-        Buffer.from(new Uint8Array(new BigInt64Array([BigInt(timestamp)]).buffer))
-      ]);
-      
-      const signature = await ed25519.sign(originalMessage, aiAgent.secretKey.slice(0, 32));
-      
-      // Submit with different evidence hash (tampered message)
-      const tamperedEvidenceHash = crypto.randomBytes(32);
-      
+      // Sign the original verdict, then submit a different evidence hash
+      const originalPayload = verificationPayload(true, 1);
+      const tamperedPayload = { ...originalPayload, evidenceHash: Array.from(crypto.randomBytes(32)) };
+
       try {
-        await program.methods
-          .submitVerification(
-            result,
-            Array.from(tamperedEvidenceHash),
-            new anchor.BN(timestamp),
-            Array.from(signature)
-          )
-          .accounts({
-            escrow: escrowPDA,
-            aiAgent: aiAgent.publicKey,
-          })
-          .rpc();
-        
+        await submitVerification(
+          program,
+          escrowId,
+          aiAgent,
+          requestId,
+          tamperedPayload,
+          { signedPayload: originalPayload }
+        );
+
         expect.fail("Should have rejected tampered message");
       } catch (error) {
         expect(error.message).to.include("InvalidSignature");
       }
     });
 
+    it("should reject a verdict signed for another request", async () => {
+      try {
+        await submitVerification(
+          program,
+          escrowId,
+          aiAgent,
+          crypto.randomBytes(32),
+          verificationPayload(true, 1)
+        );
+
+        expect.fail("Should have rejected a verdict for another request");
+      } catch (error) {
+        expect(error.message).to.include("InvalidChainlinkRequest");
+      }
+    });
+
     it("should reject old timestamp", async () => {
-      const result = true;
-      const evidenceHash = crypto.randomBytes(32);
-      const oldTimestamp = Math.floor(Date.now() / 1000) - 600; // 10 minutes ago
-      
-      const message = Buffer.concat([
-        Buffer.from(escrowId),
-        Buffer.from([result ? 1 : 0]),
-        Buffer.from(evidenceHash),
-        Buffer.from(new Uint8Array(new BigInt64Array([BigInt(oldTimestamp)]).buffer))
-      ]);
-      
-      const signature = await ed25519.sign(message, aiAgent.secretKey.slice(0, 32));
-      
+      const oldTimestamp = now() - 600; // 10 minutes ago
+
       try {
-        await program.methods
-          .submitVerification(
-            result,
-            Array.from(evidenceHash),
-            new anchor.BN(oldTimestamp),
-            Array.from(signature)
-          )
-          .accounts({
-            escrow: escrowPDA,
-            aiAgent: aiAgent.publicKey,
-          })
-          .rpc();
-        
+        await submitVerification(
+          program,
+          escrowId,
+          aiAgent,
+          requestId,
+          verificationPayload(true, 1, { timestamp: new anchor.BN(oldTimestamp) })
+        );
+
         expect.fail("Should have rejected old timestamp");
       } catch (error) {
-        expect.error.message).to.include("TimestampTooOld");
+        expect(error.message).to.include("TimestampTooOld");
+      }
+    });
+
+    it("should reject an unsupported payload version", async () => {
+      try {
+        await submitVerification(
+          program,
+          escrowId,
+          aiAgent,
+          requestId,
+          verificationPayload(true, 1, { version: 2 })
+        );
+
+        expect.fail("Should have rejected payload version");
+      } catch (error) {
+        expect(error.message).to.include("UnsupportedPayloadVersion");
       }
     });
   });
@@ -816,467 +449,181 @@ describe("AetherLock Escrow Protocol", () => {
   describe("Fee Calculation and Distribution", () => {
     let escrowId: Buffer;
     let escrowPDA: PublicKey;
-    let vaultPDA: PublicKey;
-    
+
     beforeEach(async () => {
       escrowId = crypto.randomBytes(32);
-      
-      [escrowPDA] = PublicKey.findProgramAddressSync(
-        [Buffer.from("escrow"), escrowId],
-        program.programId
-      );
-      
-      [vaultPDA] = PublicKey.findProgramAddressSync(
-        [Buffer.from("vault"), escrowPDA.toBuffer()],
-        program.programId
-      );
+      escrowPDA = escrowPda(program, escrowId);
     });
 
-    it("should calculate 2% protocol fee correctly", async () => {
+    it("should calculate 10% protocol fee correctly", async () => {
       const testAmount = 5000000; // 5 tokens
-      const expectedFee = 100000; // 2% of 5 tokens
-      
-      const expiry = Math.floor(Date.now() / 1000) + 3600;
-      const metadataHash = crypto.randomBytes(32);
-      
-      await program.methods
-        .initializeEscrow(
-          Array.from(escrowId),
-          seller.publicKey,
-          new anchor.BN(testAmount),
-          new anchor.BN(expiry),
-          Array.from(metadataHash),
-          aiAgent.publicKey
-        )
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          tokenMint: tokenMint,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
-      
+      const expectedFeeAmount = 500000; // 10% of 5000000
+
+      await initializeEscrow(program, parties, escrowId, new anchor.BN(testAmount));
+
       const escrow = await program.account.escrowAccount.fetch(escrowPDA);
-      expect(escrow.feeAmount.toNumber()).to.equal(expectedFee);
+      expect(escrow.feeAmount.toNumber()).to.equal(expectedFeeAmount);
+      expect(escrow.feeBps).to.equal(1000);
     });
 
     it("should distribute funds correctly on release", async () => {
       const testAmount = 2000000; // 2 tokens
-      const expectedFee = 40000; // 2% of 2 tokens
-      const expectedSellerAmount = 1960000; // Amount after fee
-      
-      // Setup complete escrow flow
-      const expiry = Math.floor(Date.now() / 1000) + 3600;
-      const metadataHash = crypto.randomBytes(32);
-      
-      await program.methods
-        .initializeEscrow(
-          Array.from(escrowId),
-          seller.publicKey,
-          new anchor.BN(testAmount),
-          new anchor.BN(expiry),
-          Array.from(metadataHash),
-          aiAgent.publicKey
-        )
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          tokenMint: tokenMint,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
-      
-      await program.methods
-        .depositFunds()
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          escrowVault: vaultPDA,
-          buyerTokenAccount: buyerTokenAccount,
-          tokenProgram: TOKEN_PROGRAM_ID,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
-      
-      // AI verification
-      const result = true;
-      const evidenceHash = crypto.randomBytes(32);
-      const timestamp = Math.floor(Date.now() / 1000);
-      
-      const message = Buffer.concat([
-        Buffer.from(escrowId),
-        Buffer.from([result ? 1 : 0]),
-        Buffer.from(evidenceHash),
-        Buffer.from(new Uint8Array(new BigInt64Array([BigInt(timestamp)]).buffer))
-      ]);
-      
-      const signature = await ed25519.sign(message, aiAgent.secretKey.slice(0, 32));
-      
-      await program.methods
-        .submitVerification(
-          result,
-          Array.from(evidenceHash),
-          new anchor.BN(timestamp),
-          Array.from(signature)
-        )
-        .accounts({
-          escrow: escrowPDA,
-          aiAgent: aiAgent.publicKey,
-        })
-        .rpc();
-      
+      const expectedFeeAmount = expectedFee(testAmount);
+      const expectedSellerAmount = testAmount - expectedFeeAmount;
+
+      await createFundedEscrow(program, parties, escrowId, new anchor.BN(testAmount));
+      await verifyMilestone(program, parties, escrowId, true);
+
       // Get initial balances
       const initialSellerAccount = await getAccount(provider.connection, sellerTokenAccount);
       const initialTreasuryAccount = await getAccount(provider.connection, protocolTreasury);
       const initialSellerBalance = Number(initialSellerAccount.amount);
       const initialTreasuryBalance = Number(initialTreasuryAccount.amount);
-      
-      // Release funds
-      await program.methods
-        .releaseFunds()
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          escrowVault: vaultPDA,
-          sellerTokenAccount: sellerTokenAccount,
-          protocolTreasury: protocolTreasury,
-          tokenProgram: TOKEN_PROGRAM_ID,
-        })
-        .signers([buyer])
-        .rpc();
-      
-      // Verify fund distribution
+
+      await releaseFunds(program, parties, escrowId);
+
+      // Verify final balances
       const finalSellerAccount = await getAccount(provider.connection, sellerTokenAccount);
       const finalTreasuryAccount = await getAccount(provider.connection, protocolTreasury);
-      
+
       expect(Number(finalSellerAccount.amount)).to.equal(initialSellerBalance + expectedSellerAmount);
-      expect(Number(finalTreasuryAccount.amount)).to.equal(initialTreasuryBalance + expectedFee);
+      expect(Number(finalTreasuryAccount.amount)).to.equal(initialTreasuryBalance + expectedFeeAmount);
     });
 
     it("should handle edge case amounts correctly", async () => {
-      // Test with amount that doesn't divide evenly by 100
-      const testAmount = 999; // Should result in fee of 19 (rounded down)
-      const expectedFee = 19;
-      
-      const expiry = Math.floor(Date.now() / 1000) + 3600;
-      const metadataHash = crypto.randomBytes(32);
-      
-      await program.methods
-        .initializeEscrow(
-          Array.from(escrowId),
-          seller.publicKey,
-          new anchor.BN(testAmount),
-          new anchor.BN(expiry),
-          Array.from(metadataHash),
-          aiAgent.publicKey
-        )
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          tokenMint: tokenMint,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
-      
+      // Test with amount that doesn't divide evenly by 10
+      const testAmount = 999; // Should result in fee of 99 (rounded down)
+      const expectedFeeAmount = 99;
+
+      await initializeEscrow(program, parties, escrowId, new anchor.BN(testAmount));
+
       const escrow = await program.account.escrowAccount.fetch(escrowPDA);
-      expect(escrow.feeAmount.toNumber()).to.equal(expectedFee);
+      expect(escrow.feeAmount.toNumber()).to.equal(expectedFeeAmount);
     });
   });
 
   describe("Dispute Resolution", () => {
     let escrowId: Buffer;
     let escrowPDA: PublicKey;
-    let vaultPDA: PublicKey;
-    
+
     beforeEach(async () => {
       escrowId = crypto.randomBytes(32);
-      
-      [escrowPDA] = PublicKey.findProgramAddressSync(
-        [Buffer.from("escrow"), escrowId],
-        program.programId
-      );
-      
-      [vaultPDA] = PublicKey.findProgramAddressSync(
-        [Buffer.from("vault"), escrowPDA.toBuffer()],
-        program.programId
-      );
-      
+
       // Setup funded escrow
-      const expiry = Math.floor(Date.now() / 1000) + 3600;
-      const metadataHash = crypto.randomBytes(32);
-      
-      await program.methods
-        .initializeEscrow(
-          Array.from(escrowId),
-          seller.publicKey,
-          new anchor.BN(ESCROW_AMOUNT),
-          new anchor.BN(expiry),
-          Array.from(metadataHash),
-          aiAgent.publicKey
-        )
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          tokenMint: tokenMint,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
-      
-      await program.methods
-        .depositFunds()
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          escrowVault: vaultPDA,
-          buyerTokenAccount: buyerTokenAccount,
-          tokenProgram: TOKEN_PROGRAM_ID,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
+      escrowPDA = await createFundedEscrow(program, parties, escrowId, new anchor.BN(ESCROW_AMOUNT));
     });
 
-    it("should allow authorized admin to resolve dispute in favor of seller", async () => {
-      // Raise dispute
-      const reasonHash = crypto.randomBytes(32);
-      
-      await program.methods
-        .raiseDispute(Array.from(reasonHash))
-        .accounts({
-          participant: buyer.publicKey,
-          escrow: escrowPDA,
-        })
-        .signers([buyer])
-        .rpc();
-      
-      // Admin resolves in favor of seller
-      await program.methods
-        .resolveDispute({ favorSeller: {} })
-        .accounts({
-          admin: admin1.publicKey,
-          escrow: escrowPDA,
-          config: configPDA,
-        })
-        .signers([admin1])
-        .rpc();
-      
+    it("should let the panel resolve a dispute in favor of seller", async () => {
+      await raiseDispute(program, parties, escrowId, buyer);
+
+      const initialSellerBalance = Number((await getAccount(provider.connection, sellerTokenAccount)).amount);
+
+      await castDisputeVotes(program, escrowId, { favorSeller: {} });
+      await finalizeDispute(program, parties, escrowId);
+
       const escrow = await program.account.escrowAccount.fetch(escrowPDA);
       expect(escrow.verificationResult).to.equal(true);
       expect(escrow.disputeRaised).to.be.false;
-      expect(escrow.status).to.deep.equal({ verified: {} });
+      expect(escrow.status).to.deep.equal({ resolved: {} });
+
+      const finalSellerBalance = Number((await getAccount(provider.connection, sellerTokenAccount)).amount);
+      expect(finalSellerBalance).to.equal(initialSellerBalance + SELLER_AMOUNT);
     });
 
-    it("should allow authorized admin to resolve dispute in favor of buyer", async () => {
-      // Raise dispute
-      const reasonHash = crypto.randomBytes(32);
-      
-      await program.methods
-        .raiseDispute(Array.from(reasonHash))
-        .accounts({
-          participant: seller.publicKey,
-          escrow: escrowPDA,
-        })
-        .signers([seller])
-        .rpc();
-      
-      // Admin resolves in favor of buyer
-      await program.methods
-        .resolveDispute({ favorBuyer: {} })
-        .accounts({
-          admin: admin1.publicKey,
-          escrow: escrowPDA,
-          config: configPDA,
-        })
-        .signers([admin1])
-        .rpc();
-      
+    it("should let the panel resolve a dispute in favor of buyer", async () => {
+      await raiseDispute(program, parties, escrowId, seller);
+
+      const initialBuyerBalance = Number((await getAccount(provider.connection, buyerTokenAccount)).amount);
+
+      await castDisputeVotes(program, escrowId, { favorBuyer: {} });
+      await finalizeDispute(program, parties, escrowId);
+
       const escrow = await program.account.escrowAccount.fetch(escrowPDA);
       expect(escrow.verificationResult).to.equal(false);
       expect(escrow.disputeRaised).to.be.false;
-      expect(escrow.status).to.deep.equal({ verified: {} });
+      expect(escrow.status).to.deep.equal({ resolved: {} });
+
+      // The buyer gets the whole tranche back, with no fee
+      const finalBuyerBalance = Number((await getAccount(provider.connection, buyerTokenAccount)).amount);
+      expect(finalBuyerBalance).to.equal(initialBuyerBalance + ESCROW_AMOUNT);
     });
 
-    it("should reject dispute resolution from unauthorized user", async () => {
-      // Raise dispute
-      const reasonHash = crypto.randomBytes(32);
-      
-      await program.methods
-        .raiseDispute(Array.from(reasonHash))
-        .accounts({
-          participant: buyer.publicKey,
-          escrow: escrowPDA,
-        })
-        .signers([buyer])
-        .rpc();
-      
-      // Unauthorized user tries to resolve
+    it("should split the tranche on a split outcome", async () => {
+      await raiseDispute(program, parties, escrowId, buyer);
+
+      const initialBuyerBalance = Number((await getAccount(provider.connection, buyerTokenAccount)).amount);
+      const initialSellerBalance = Number((await getAccount(provider.connection, sellerTokenAccount)).amount);
+
+      await castDisputeVotes(program, escrowId, { split: { buyerBps: 4000 } });
+      await finalizeDispute(program, parties, escrowId);
+
+      // Fee is charged on the seller's share only
+      const buyerShare = ESCROW_AMOUNT * 0.4;
+      const sellerShare = ESCROW_AMOUNT - buyerShare;
+      const finalBuyerBalance = Number((await getAccount(provider.connection, buyerTokenAccount)).amount);
+      const finalSellerBalance = Number((await getAccount(provider.connection, sellerTokenAccount)).amount);
+      expect(finalBuyerBalance).to.equal(initialBuyerBalance + buyerShare);
+      expect(finalSellerBalance).to.equal(initialSellerBalance + sellerShare - expectedFee(sellerShare));
+    });
+
+    it("should reject votes from arbitrators not on the panel", async () => {
+      await raiseDispute(program, parties, escrowId, buyer);
+
       try {
         await program.methods
-          .resolveDispute({ favorSeller: {} })
-          .accounts({
-            admin: unauthorizedUser.publicKey,
+          .castDisputeVote({ favorSeller: {} })
+          .accountsPartial({
+            arbitrator: unauthorizedUser.publicKey,
             escrow: escrowPDA,
-            config: configPDA,
+            dispute: disputePda(program, escrowPDA),
           })
           .signers([unauthorizedUser])
           .rpc();
-        
-        expect.fail("Should have rejected unauthorized admin");
+
+        expect.fail("Should have rejected a vote from outside the panel");
       } catch (error) {
-        expect(error.message).to.include("UnauthorizedAdmin");
+        expect(error.message).to.include("NotOnPanel");
       }
     });
 
     it("should prevent operations during active dispute", async () => {
-      // Raise dispute
-      const reasonHash = crypto.randomBytes(32);
-      
-      await program.methods
-        .raiseDispute(Array.from(reasonHash))
-        .accounts({
-          participant: buyer.publicKey,
-          escrow: escrowPDA,
-        })
-        .signers([buyer])
-        .rpc();
-      
-      // Try to submit verification during dispute
-      const result = true;
-      const evidenceHash = crypto.randomBytes(32);
-      const timestamp = Math.floor(Date.now() / 1000);
-      
-      const message = Buffer.concat([
-        Buffer.from(escrowId),
-        Buffer.from([result ? 1 : 0]),
-        Buffer.from(evidenceHash),
-        Buffer.from(new Uint8Array(new BigInt64Array([BigInt(timestamp)]).buffer))
-      ]);
-      
-      const signature = await ed25519.sign(message, aiAgent.secretKey.slice(0, 32));
-      
+      await raiseDispute(program, parties, escrowId, buyer);
+
+      // Try to start verification during dispute
       try {
-        await program.methods
-          .submitVerification(
-            result,
-            Array.from(evidenceHash),
-            new anchor.BN(timestamp),
-            Array.from(signature)
-          )
-          .accounts({
-            escrow: escrowPDA,
-            aiAgent: aiAgent.publicKey,
-          })
-          .rpc();
-        
+        await requestVerification(program, parties, escrowId);
+
         expect.fail("Should have prevented verification during dispute");
       } catch (error) {
         expect(error.message).to.include("DisputeActive");
       }
     });
 
-    it("should expire dispute after deadline and prevent further dispute actions", async () => {
-      // Setup funded escrow and raise dispute
-      const expiry = Math.floor(Date.now() / 1000) + 3600;
-      const metadataHash = crypto.randomBytes(32);
-      await program.methods
-        .initializeEscrow(
-          Array.from(escrowId),
-          seller.publicKey,
-          new anchor.BN(ESCROW_AMOUNT),
-          new anchor.BN(expiry),
-          Array.from(metadataHash),
-          aiAgent.publicKey
-        )
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          tokenMint: tokenMint,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
-      [vaultPDA, vaultBump] = PublicKey.findProgramAddressSync(
-        [Buffer.from("vault"), escrowPDA.toBuffer()],
-        program.programId
-      );
-      await program.methods
-        .depositFunds()
-        .accounts({
-          buyer: buyer.publicKey,
-          escrow: escrowPDA,
-          escrowVault: vaultPDA,
-          buyerTokenAccount: buyerTokenAccount,
-          tokenProgram: TOKEN_PROGRAM_ID,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([buyer])
-        .rpc();
-      // Raise dispute
-      const reasonHash = crypto.randomBytes(32);
-      await program.methods
-        .raiseDispute(Array.from(reasonHash))
-        .accounts({
-          participant: buyer.publicKey,
-          escrow: escrowPDA,
-        })
-        .signers([buyer])
-        .rpc();
-      // Fast-forward time past dispute deadline
-      const escrowBefore = await program.account.escrowAccount.fetch(escrowPDA);
-      const deadline = escrowBefore.disputeDeadline;
-      await provider.connection.confirmTransaction(
-        await provider.connection.requestAirdrop(buyer.publicKey, 1 * anchor.web3.LAMPORTS_PER_SOL)
-      );
-      // Simulate time passing (mock clock sysvar or use test validator time travel if available)
-      // For demonstration, attempt to resolve dispute after deadline
+    it("should not finalize before quorum or the dispute deadline", async () => {
+      await raiseDispute(program, parties, escrowId, buyer);
+      await castDisputeVotes(program, escrowId, { favorSeller: {} }, 1);
+
       try {
-        await program.methods
-          .resolveDispute({ favorSeller: {} })
-          .accounts({
-            admin: admin1.publicKey,
-            escrow: escrowPDA,
-            config: configPDA,
-          })
-          .signers([admin1])
-          .rpc();
-        // If contract allows, check state
-        const escrowAfter = await program.account.escrowAccount.fetch(escrowPDA);
-        expect(escrowAfter.disputeRaised).to.be.false;
-        expect(escrowAfter.status).to.not.deep.equal({ disputed: {} });
+        await finalizeDispute(program, parties, escrowId);
+
+        expect.fail("Should have waited for quorum or the deadline");
       } catch (error) {
-        expect(error.message).to.include("DisputeExpired");
+        expect(error.message).to.include("QuorumNotReached");
       }
+
+      const escrow = await program.account.escrowAccount.fetch(escrowPDA);
+      expect(escrow.status).to.deep.equal({ disputed: {} });
     });
   });
 
   describe("Error Conditions", () => {
     it("should reject operations on invalid escrow states", async () => {
       const escrowId = crypto.randomBytes(32);
-      const [escrowPDA] = PublicKey.findProgramAddressSync(
-        [Buffer.from("escrow"), escrowId],
-        program.programId
-      );
-      
+
       // Try to deposit funds on non-existent escrow
       try {
-        await program.methods
-          .depositFunds()
-          .accounts({
-            buyer: buyer.publicKey,
-            escrow: escrowPDA,
-            escrowVault: Keypair.generate().publicKey,
-            buyerTokenAccount: buyerTokenAccount,
-            tokenProgram: TOKEN_PROGRAM_ID,
-            systemProgram: SystemProgram.programId,
-          })
-          .signers([buyer])
-          .rpc();
-        
+        await depositFunds(program, parties, escrowId);
+
         expect.fail("Should have rejected operation on non-existent escrow");
       } catch (error) {
         // Expected to fail due to account not existing
@@ -1284,41 +631,19 @@ describe("AetherLock Escrow Protocol", () => {
       }
     });
 
-    it("should handle math overflow protection", async () => {
+    it("should reject amounts outside the mint's limits", async () => {
       const escrowId = crypto.randomBytes(32);
-      const [escrowPDA] = PublicKey.findProgramAddressSync(
-        [Buffer.from("escrow"), escrowId],
-        program.programId
-      );
-      
-      // Try to create escrow with maximum u64 value (should cause overflow in fee calculation)
+
+      // Maximum u64 value is far above the mint's configured cap
       const maxU64 = new anchor.BN("18446744073709551615"); // 2^64 - 1
-      const expiry = Math.floor(Date.now() / 1000) + 3600;
-      const metadataHash = crypto.randomBytes(32);
-      
+
       try {
-        await program.methods
-          .initializeEscrow(
-            Array.from(escrowId),
-            seller.publicKey,
-            maxU64,
-            new anchor.BN(expiry),
-            Array.from(metadataHash),
-            aiAgent.publicKey
-          )
-          .accounts({
-            buyer: buyer.publicKey,
-            escrow: escrowPDA,
-            tokenMint: tokenMint,
-            systemProgram: SystemProgram.programId,
-          })
-          .signers([buyer])
-          .rpc();
-        
-        expect.fail("Should have rejected due to math overflow");
+        await initializeEscrow(program, parties, escrowId, maxU64);
+
+        expect.fail("Should have rejected amount above the mint's cap");
       } catch (error) {
-        expect(error.message).to.include("MathOverflow");
+        expect(error.message).to.include("EscrowAmountOutOfRange");
       }
     });
   });
-});
\ No newline at end of file
+});
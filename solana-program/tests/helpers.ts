@@ -0,0 +1,512 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { AetherlockEscrow } from "../target/types/aetherlock_escrow";
+import {
+  PublicKey,
+  Keypair,
+  SystemProgram,
+  Ed25519Program,
+  SYSVAR_INSTRUCTIONS_PUBKEY
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  getOrCreateAssociatedTokenAccount
+} from "@solana/spl-token";
+import * as crypto from "crypto";
+
+// Shared by every suite: the protocol config is a singleton PDA, so all suites
+// run against one config owned by the provider wallet
+export const VERIFICATION_PAYLOAD_VERSION = 3;
+export const DEFAULT_FEE_BPS = 1000; // 10%
+export const REVIEW_WINDOW = 24 * 60 * 60; // 24 hours
+export const DOMAIN_SEPARATOR = crypto.createHash("sha256").update("aetherlock:localnet").digest();
+
+// Round-robin panels are drawn from this pool; parties never sit on their own panel
+export const arbitrators = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+
+// PKCS#8 DER prefix for a raw 32-byte Ed25519 seed
+const ED25519_PKCS8_PREFIX = Buffer.from("302e020100300506032b657004220420", "hex");
+
+export type EscrowProgram = Program<AetherlockEscrow>;
+
+export interface EscrowParties {
+  buyer: Keypair;
+  seller: Keypair;
+  aiAgent: Keypair;
+  tokenMint: PublicKey;
+  buyerTokenAccount: PublicKey;
+  sellerTokenAccount: PublicKey;
+}
+
+export interface VerificationPayload {
+  version: number;
+  result: boolean;
+  confidenceBps: number;
+  evidenceHash: number[];
+  modelHash: number[];
+  reportHash: number[];
+  nonce: anchor.BN;
+  timestamp: anchor.BN;
+}
+
+export function now(): number {
+  return Math.floor(Date.now() / 1000);
+}
+
+export function configPda(program: EscrowProgram): PublicKey {
+  return PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId)[0];
+}
+
+export function escrowPda(program: EscrowProgram, escrowId: Buffer): PublicKey {
+  return PublicKey.findProgramAddressSync([Buffer.from("escrow"), escrowId], program.programId)[0];
+}
+
+export function vaultPda(program: EscrowProgram, escrow: PublicKey): PublicKey {
+  return PublicKey.findProgramAddressSync([Buffer.from("vault"), escrow.toBuffer()], program.programId)[0];
+}
+
+export function receiptPda(program: EscrowProgram, escrowId: Buffer): PublicKey {
+  return PublicKey.findProgramAddressSync([Buffer.from("receipt"), escrowId], program.programId)[0];
+}
+
+export function supportedMintPda(program: EscrowProgram, mint: PublicKey): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("supported_mint"), mint.toBuffer()],
+    program.programId
+  )[0];
+}
+
+export function agentPda(program: EscrowProgram, agent: PublicKey): PublicKey {
+  return PublicKey.findProgramAddressSync([Buffer.from("agent"), agent.toBuffer()], program.programId)[0];
+}
+
+export function disputePda(program: EscrowProgram, escrow: PublicKey, milestoneIndex = 0): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("dispute"), escrow.toBuffer(), Buffer.from([milestoneIndex])],
+    program.programId
+  )[0];
+}
+
+export function evidencePda(program: EscrowProgram, dispute: PublicKey): PublicKey {
+  return PublicKey.findProgramAddressSync([Buffer.from("evidence"), dispute.toBuffer()], program.programId)[0];
+}
+
+export function expectedFee(amount: number, feeBps = DEFAULT_FEE_BPS): number {
+  return Math.floor((amount * feeBps) / 10000);
+}
+
+// Create the protocol config on first use, seat the arbitrator pool and
+// return the config PDA. Later calls reuse the existing config.
+export async function setupProtocol(program: EscrowProgram): Promise<PublicKey> {
+  const config = configPda(program);
+  if (await program.provider.connection.getAccountInfo(config)) {
+    return config;
+  }
+
+  await program.methods
+    .initializeConfig([], Array.from(DOMAIN_SEPARATOR))
+    .accountsPartial({
+      authority: program.provider.publicKey,
+      config,
+      systemProgram: SystemProgram.programId,
+    })
+    .rpc();
+
+  for (const arbitrator of arbitrators) {
+    await program.methods
+      .addArbitrator(arbitrator.publicKey)
+      .accountsPartial({
+        admin: program.provider.publicKey,
+        config,
+      })
+      .rpc();
+  }
+
+  return config;
+}
+
+// Allow escrows in `mint` and create the treasury's token account for it
+export async function supportMint(
+  program: EscrowProgram,
+  mint: PublicKey,
+  minAmount = 0,
+  maxAmount = 0
+): Promise<PublicKey> {
+  const provider = program.provider as anchor.AnchorProvider;
+  await program.methods
+    .addSupportedMint(mint, new anchor.BN(minAmount), new anchor.BN(maxAmount), null)
+    .accountsPartial({
+      authority: provider.publicKey,
+      config: configPda(program),
+      supportedMint: supportedMintPda(program, mint),
+      systemProgram: SystemProgram.programId,
+    })
+    .rpc();
+
+  const treasury = await getOrCreateAssociatedTokenAccount(
+    provider.connection,
+    (provider.wallet as anchor.Wallet).payer,
+    mint,
+    provider.publicKey
+  );
+  return treasury.address;
+}
+
+export function treasuryAccount(program: EscrowProgram, mint: PublicKey): PublicKey {
+  return anchor.utils.token.associatedAddress({ mint, owner: program.provider.publicKey! });
+}
+
+// Create a single-milestone escrow verified by one unregistered agent
+export async function initializeEscrow(
+  program: EscrowProgram,
+  parties: EscrowParties,
+  escrowId: Buffer,
+  amount: anchor.BN,
+  metadataHash: Buffer = crypto.randomBytes(32)
+): Promise<PublicKey> {
+  const escrow = escrowPda(program, escrowId);
+  const deadline = now() + 3600;
+
+  await program.methods
+    .initializeEscrow(
+      Array.from(escrowId),
+      parties.seller.publicKey,
+      amount,
+      new anchor.BN(deadline),
+      new anchor.BN(REVIEW_WINDOW),
+      Array.from(metadataHash),
+      parties.aiAgent.publicKey,
+      [],
+      new anchor.BN(deadline),
+      null,
+      null,
+      [],
+      1,
+      0
+    )
+    .accountsPartial({
+      buyer: parties.buyer.publicKey,
+      escrow,
+      tokenMint: parties.tokenMint,
+      config: configPda(program),
+      supportedMint: supportedMintPda(program, parties.tokenMint),
+      aiAgent: null,
+      receipt: receiptPda(program, escrowId),
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([parties.buyer])
+    .rpc();
+
+  return escrow;
+}
+
+export async function acceptEscrow(program: EscrowProgram, parties: EscrowParties, escrowId: Buffer) {
+  const escrow = escrowPda(program, escrowId);
+  const { metadataHash } = await program.account.escrowAccount.fetch(escrow);
+
+  await program.methods
+    .acceptEscrow(metadataHash)
+    .accountsPartial({
+      seller: parties.seller.publicKey,
+      escrow,
+    })
+    .signers([parties.seller])
+    .rpc();
+}
+
+export async function depositFunds(program: EscrowProgram, parties: EscrowParties, escrowId: Buffer) {
+  const escrow = escrowPda(program, escrowId);
+
+  await program.methods
+    .depositFunds()
+    .accountsPartial({
+      buyer: parties.buyer.publicKey,
+      escrow,
+      tokenMint: parties.tokenMint,
+      escrowVault: vaultPda(program, escrow),
+      buyerTokenAccount: parties.buyerTokenAccount,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([parties.buyer])
+    .rpc();
+}
+
+// Create, accept and fund an escrow, returning its PDA
+export async function createFundedEscrow(
+  program: EscrowProgram,
+  parties: EscrowParties,
+  escrowId: Buffer,
+  amount: anchor.BN
+): Promise<PublicKey> {
+  const escrow = await initializeEscrow(program, parties, escrowId, amount);
+  await acceptEscrow(program, parties, escrowId);
+  await depositFunds(program, parties, escrowId);
+  return escrow;
+}
+
+// Open a Chainlink verification round for the current milestone, returning its request ID
+export async function requestVerification(
+  program: EscrowProgram,
+  parties: EscrowParties,
+  escrowId: Buffer,
+  milestoneIndex = 0
+): Promise<Buffer> {
+  const requestId = crypto.randomBytes(32);
+
+  await program.methods
+    .requestChainlinkVerification(milestoneIndex, Array.from(crypto.randomBytes(32)), Array.from(requestId))
+    .accountsPartial({
+      requester: parties.seller.publicKey,
+      escrow: escrowPda(program, escrowId),
+    })
+    .signers([parties.seller])
+    .rpc();
+
+  return requestId;
+}
+
+export function verificationPayload(
+  result: boolean,
+  nonce: number,
+  overrides: Partial<VerificationPayload> = {}
+): VerificationPayload {
+  return {
+    version: VERIFICATION_PAYLOAD_VERSION,
+    result,
+    confidenceBps: 9000,
+    evidenceHash: Array.from(crypto.randomBytes(32)),
+    modelHash: Array.from(crypto.randomBytes(32)),
+    reportHash: Array.from(crypto.randomBytes(32)),
+    nonce: new anchor.BN(nonce),
+    timestamp: new anchor.BN(now()),
+    ...overrides,
+  };
+}
+
+// Bytes an agent signs, matching `VerificationPayload::message` on chain
+export function verificationMessage(
+  program: EscrowProgram,
+  escrowId: Buffer,
+  milestoneIndex: number,
+  requestId: Buffer,
+  payload: VerificationPayload
+): Buffer {
+  const confidence = Buffer.alloc(2);
+  confidence.writeUInt16LE(payload.confidenceBps);
+  const nonce = Buffer.alloc(8);
+  nonce.writeBigUInt64LE(BigInt(payload.nonce.toString()));
+  const timestamp = Buffer.alloc(8);
+  timestamp.writeBigInt64LE(BigInt(payload.timestamp.toString()));
+
+  return Buffer.concat([
+    Buffer.from([payload.version]),
+    program.programId.toBuffer(),
+    DOMAIN_SEPARATOR,
+    escrowId,
+    Buffer.from([milestoneIndex]),
+    requestId,
+    Buffer.from([payload.result ? 1 : 0]),
+    confidence,
+    Buffer.from(payload.evidenceHash),
+    Buffer.from(payload.modelHash),
+    Buffer.from(payload.reportHash),
+    nonce,
+    timestamp,
+  ]);
+}
+
+export function ed25519Sign(message: Buffer, signer: Keypair): Buffer {
+  const privateKey = crypto.createPrivateKey({
+    key: Buffer.concat([ED25519_PKCS8_PREFIX, Buffer.from(signer.secretKey.slice(0, 32))]),
+    format: "der",
+    type: "pkcs8",
+  });
+  return crypto.sign(null, message, privateKey);
+}
+
+// Submit `payload` for the current milestone, preceded by the Ed25519 program
+// instruction that verifies it. `signedPayload` and `signature` let a test
+// make the proof and the submitted verdict disagree.
+export async function submitVerification(
+  program: EscrowProgram,
+  escrowId: Buffer,
+  agent: Keypair,
+  requestId: Buffer,
+  payload: VerificationPayload,
+  options: { signedPayload?: VerificationPayload; signature?: Buffer; milestoneIndex?: number } = {}
+) {
+  const milestoneIndex = options.milestoneIndex ?? 0;
+  const message = verificationMessage(
+    program,
+    escrowId,
+    milestoneIndex,
+    requestId,
+    options.signedPayload ?? payload
+  );
+  const signature = ed25519Sign(message, agent);
+
+  await program.methods
+    .submitVerification(
+      milestoneIndex,
+      payload,
+      Array.from(options.signature ?? signature),
+      Array.from(requestId)
+    )
+    .accountsPartial({
+      escrow: escrowPda(program, escrowId),
+      aiAgent: null,
+      signingKey: agent.publicKey,
+      unregisteredAgent: agentPda(program, agent.publicKey),
+      instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+      payer: program.provider.publicKey,
+      config: configPda(program),
+      dispute: null,
+      evidence: null,
+      systemProgram: SystemProgram.programId,
+    })
+    .preInstructions([
+      Ed25519Program.createInstructionWithPublicKey({
+        publicKey: agent.publicKey.toBytes(),
+        message,
+        signature,
+      }),
+    ])
+    .rpc();
+}
+
+// Run a full verification round with the escrow's agent
+export async function verifyMilestone(
+  program: EscrowProgram,
+  parties: EscrowParties,
+  escrowId: Buffer,
+  result: boolean,
+  nonce = 1
+) {
+  const requestId = await requestVerification(program, parties, escrowId);
+  await submitVerification(program, escrowId, parties.aiAgent, requestId, verificationPayload(result, nonce));
+}
+
+export async function releaseFunds(
+  program: EscrowProgram,
+  parties: EscrowParties,
+  escrowId: Buffer,
+  caller: Keypair = parties.buyer
+) {
+  const escrow = escrowPda(program, escrowId);
+
+  await program.methods
+    .releaseFunds(0)
+    .accountsPartial({
+      caller: caller.publicKey,
+      escrow,
+      escrowVault: vaultPda(program, escrow),
+      tokenMint: parties.tokenMint,
+      sellerTokenAccount: parties.sellerTokenAccount,
+      protocolTreasury: treasuryAccount(program, parties.tokenMint),
+      sellerWallet: null,
+      treasuryWallet: null,
+      config: configPda(program),
+      tokenProgram: TOKEN_PROGRAM_ID,
+    })
+    .signers([caller])
+    .rpc();
+}
+
+export async function refundBuyer(program: EscrowProgram, parties: EscrowParties, escrowId: Buffer) {
+  const escrow = escrowPda(program, escrowId);
+
+  await program.methods
+    .refundBuyer(0)
+    .accountsPartial({
+      buyer: parties.buyer.publicKey,
+      escrow,
+      escrowVault: vaultPda(program, escrow),
+      tokenMint: parties.tokenMint,
+      buyerTokenAccount: parties.buyerTokenAccount,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    })
+    .signers([parties.buyer])
+    .rpc();
+}
+
+// Raise a dispute over the current milestone. The protocol charges no bond by default.
+export async function raiseDispute(
+  program: EscrowProgram,
+  parties: EscrowParties,
+  escrowId: Buffer,
+  participant: Keypair,
+  reasonHash: Buffer = crypto.randomBytes(32)
+): Promise<PublicKey> {
+  const escrow = escrowPda(program, escrowId);
+  const dispute = disputePda(program, escrow);
+
+  await program.methods
+    .raiseDispute(0, Array.from(reasonHash))
+    .accountsPartial({
+      participant: participant.publicKey,
+      escrow,
+      dispute,
+      evidence: evidencePda(program, dispute),
+      config: configPda(program),
+      tokenMint: parties.tokenMint,
+      raiserTokenAccount: null,
+      bondVault: null,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([participant])
+    .rpc();
+
+  return dispute;
+}
+
+// Have the first `count` members of the dispute's panel vote for `outcome`
+export async function castDisputeVotes(
+  program: EscrowProgram,
+  escrowId: Buffer,
+  outcome: any,
+  count = 2
+) {
+  const escrow = escrowPda(program, escrowId);
+  const dispute = disputePda(program, escrow);
+  const { panel } = await program.account.dispute.fetch(dispute);
+
+  for (const member of panel.slice(0, count)) {
+    const arbitrator = arbitrators.find((keypair) => keypair.publicKey.equals(member))!;
+    await program.methods
+      .castDisputeVote(outcome)
+      .accountsPartial({
+        arbitrator: arbitrator.publicKey,
+        escrow,
+        dispute,
+      })
+      .signers([arbitrator])
+      .rpc();
+  }
+}
+
+export async function finalizeDispute(program: EscrowProgram, parties: EscrowParties, escrowId: Buffer) {
+  const escrow = escrowPda(program, escrowId);
+
+  await program.methods
+    .finalizeDispute()
+    .accountsPartial({
+      caller: program.provider.publicKey,
+      escrow,
+      dispute: disputePda(program, escrow),
+      config: configPda(program),
+      escrowVault: vaultPda(program, escrow),
+      tokenMint: parties.tokenMint,
+      buyerTokenAccount: parties.buyerTokenAccount,
+      sellerTokenAccount: parties.sellerTokenAccount,
+      protocolTreasury: treasuryAccount(program, parties.tokenMint),
+      buyerWallet: null,
+      sellerWallet: null,
+      treasuryWallet: null,
+      bondVault: null,
+      raiser: null,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    })
+    .rpc();
+}